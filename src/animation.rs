@@ -1,6 +1,6 @@
 use bevy::{prelude::*};
 
-use crate::{GameState};
+use crate::{GameState, settings::Settings};
 
 
 const ANIMATION_TIMER: f32 = 8.0 / 60.0;
@@ -31,9 +31,23 @@ impl Animation {
             self.act_frame_index = 0;
             return;
         }
+        if self.timer.mode() == TimerMode::Once {
+            if self.act_frame_index + 1 >= self.frames.len() {
+                return;
+            }
+            // a once timer finishes a single time, it is restarted for every remaining frame
+            self.timer.reset();
+        }
         self.act_frame_index += 1;
     }
 
+    /// True once the last frame of a non repeating animation was shown for a whole frame time.
+    pub fn finished(&self) -> bool {
+        self.timer.mode() == TimerMode::Once
+            && self.act_frame_index + 1 >= self.frames.len()
+            && self.timer.finished()
+    }
+
     pub fn get_frame(&self) -> Option<Rect> {
         self.frames.get(self.act_frame_index).and_then(|f| Some(f.clone()))
     }
@@ -75,8 +89,12 @@ fn update_animation(
 
 fn update_animate_rotation(
     timer: Res<Time>,
+    settings: Res<Settings>,
     mut anim_q: Query<(&mut Transform, &AnimateRotation)>
 ) {
+    if settings.reduced_motion {
+        return;
+    }
     for (mut transform, animate_rotation) in anim_q.iter_mut() {
         transform.rotate(Quat::from_rotation_z(animate_rotation.angular_vel * timer.delta_seconds()));
     }
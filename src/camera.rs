@@ -1,23 +1,67 @@
-use bevy::{prelude::*};
+use bevy::{prelude::*, transform::TransformSystem};
 
-use crate::{player::{update_movables, PLAYER_CAMERA_OFFSET}, GameState};
+use crate::{player::PLAYER_CAMERA_OFFSET, GameState, simulation::{self, SimulationSet}, settings::{Settings, CameraMode}, SCREEN_WIDTH};
+
+/// How fast the smooth camera closes the sideways gap, per second.
+const CAMERA_SMOOTHING: f32 = 6.0;
 
 #[derive(Component)]
 pub struct CameraFocus;
 
+/// Sideways camera position of the last two ticks, eased with the simulation and interpolated like `Interpolated`.
+#[derive(Resource, Default)]
+struct CameraTrack {
+    previous: f32,
+    current: f32,
+}
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(
-                (
-                  update_camera.after(update_movables),
-                ).in_set(OnUpdate(GameState::Playing)));
+            .init_resource::<CameraTrack>()
+            .add_system(
+                track_focus
+                    .in_set(SimulationSet::End)
+                    .in_schedule(CoreSchedule::FixedUpdate)
+                    .run_if(in_state(GameState::Playing)))
+            .add_system(
+                update_camera
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(simulation::interpolate)
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(in_state(GameState::Playing)));
     }
 }
 
+fn track_focus(
+  fixed_time: Res<FixedTime>,
+  settings: Res<Settings>,
+  mut track: ResMut<CameraTrack>,
+  focus_q: Query<&Transform, With<CameraFocus>>,
+) {
+  let Ok(focus_transform) = focus_q.get_single() else {
+      return;
+  };
+  let gap = focus_transform.translation.x - track.current;
+  track.previous = track.current;
+  // a new race starts far away, the camera jumps there instead of sliding over
+  match settings.camera {
+      CameraMode::Smooth if gap.abs() < SCREEN_WIDTH => {
+          track.current += gap * (1.0 - (-CAMERA_SMOOTHING * fixed_time.period.as_secs_f32()).exp());
+      },
+      _ => {
+          track.current = focus_transform.translation.x;
+          track.previous = track.current;
+      },
+  }
+}
+
 fn update_camera(
+  fixed_time: Res<FixedTime>,
+  settings: Res<Settings>,
+  track: Res<CameraTrack>,
   focus_q: Query<&Transform, (With<CameraFocus>, Without<Camera>)>,
   mut camera_q: Query<&mut Transform, With<Camera>>,
 ) {
@@ -27,6 +71,12 @@ fn update_camera(
   let Ok(mut camera_transform) = camera_q.get_single_mut() else {
       return;
   };
-  camera_transform.translation.x = focus_transform.translation.x;
+  camera_transform.translation.x = match settings.camera {
+      CameraMode::Smooth => {
+          let alpha = (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).min(1.0);
+          track.previous + (track.current - track.previous) * alpha
+      },
+      CameraMode::Locked => focus_transform.translation.x,
+  };
   camera_transform.translation.y = focus_transform.translation.y - PLAYER_CAMERA_OFFSET;
-}
\ No newline at end of file
+}
@@ -37,12 +37,12 @@ fn line_intersection(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> Option<Vec2> {
         return None;
     }
     let c = b1 - a1;
-    let t = c.dot(d) / b_dot_d;
-    if t < 0.0 || t > 1.0 {
+    let t = c.perp_dot(d) / b_dot_d;
+    if !(0.0..=1.0).contains(&t) {
         return None;
     }
-    let u = c.dot(b) / b_dot_d;
-    if u < 0.0 || u > 1.0 {
+    let u = c.perp_dot(b) / b_dot_d;
+    if !(0.0..=1.0).contains(&u) {
         return None;
     }
 
@@ -85,33 +85,29 @@ impl Collidable {
         self.bottom = y - self.height_half + self.offset_y;
     }
 
+    pub fn bounds(&self) -> Rect {
+        Rect::new(self.left, self.bottom, self.right, self.top)
+    }
+
     pub fn intersect(&self, other: &Collidable) -> bool {
-        !(self.right < other.left || other.right < self.left ||
-        self.bottom > other.top || other.bottom > self.top)
+        self.intersect_rect(other.bounds())
     }
 
-    pub fn intersect_line(&self, line_start: Vec2, line_end: Vec2) -> Option<Vec2> {
-        let edge1 = vec2(self.top, self.left);
-        let edge2 = vec2(self.top, self.right);
-        if let Some(intersection) = line_intersection(edge1, edge2, line_start, line_end) {
-            return Some(intersection);
-        }
-        let edge1 = vec2(self.top, self.left);
-        let edge2 = vec2(self.bottom, self.left);
-        if let Some(intersection) = line_intersection(edge1, edge2, line_start, line_end) {
-            return Some(intersection);
-        }
-        let edge1 = vec2(self.bottom, self.left);
-        let edge2 = vec2(self.bottom, self.right);
-        if let Some(intersection) = line_intersection(edge1, edge2, line_start, line_end) {
-            return Some(intersection);
-        }
-        let edge1 = vec2(self.top, self.right);
-        let edge2 = vec2(self.bottom, self.right);
-        if let Some(intersection) = line_intersection(edge1, edge2, line_start, line_end) {
-            return Some(intersection);
-        }
+    pub fn intersect_rect(&self, rect: Rect) -> bool {
+        !(self.right < rect.min.x || rect.max.x < self.left ||
+        self.bottom > rect.max.y || rect.min.y > self.top)
+    }
 
-        None
+    /// Point closest to `line_start` where the segment crosses an edge of the box.
+    pub fn intersect_line(&self, line_start: Vec2, line_end: Vec2) -> Option<Vec2> {
+        let edges = [
+            (vec2(self.left, self.top), vec2(self.right, self.top)),
+            (vec2(self.left, self.top), vec2(self.left, self.bottom)),
+            (vec2(self.left, self.bottom), vec2(self.right, self.bottom)),
+            (vec2(self.right, self.top), vec2(self.right, self.bottom)),
+        ];
+        edges.iter()
+            .filter_map(|&(edge1, edge2)| line_intersection(edge1, edge2, line_start, line_end))
+            .min_by(|a, b| a.distance_squared(line_start).total_cmp(&b.distance_squared(line_start)))
     }
 }
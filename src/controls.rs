@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    GameResources,
+    despawn,
+    gamepad::ButtonPresses,
+    NORMAL_BUTTON,
+    SELECTED_BUTTON,
+    storage::{GameStorage, Storage, load_ron, save_ron},
+};
+
+const KEY_BINDINGS_KEY: &str = "controls";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    SteerLeft,
+    SteerRight,
+    Tuck,
+    Brake,
+    Pause,
+    ToggleMusic,
+    ToggleSfx,
+    ToggleDebug,
+    ToggleYetiControl,
+    DebugStun,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::SteerLeft,
+        Action::SteerRight,
+        Action::Tuck,
+        Action::Brake,
+        Action::Pause,
+        Action::ToggleMusic,
+        Action::ToggleSfx,
+        Action::ToggleDebug,
+        Action::ToggleYetiControl,
+        Action::DebugStun,
+    ];
+
+    /// Actions listed on the controls screen, the debug ones stay out of sight.
+    pub const REBINDABLE: [Action; 7] = [
+        Action::SteerLeft,
+        Action::SteerRight,
+        Action::Tuck,
+        Action::Brake,
+        Action::Pause,
+        Action::ToggleMusic,
+        Action::ToggleSfx,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::SteerLeft => "Steer left",
+            Action::SteerRight => "Steer right",
+            Action::Tuck => "Tuck",
+            Action::Brake => "Brake",
+            Action::Pause => "Pause",
+            Action::ToggleMusic => "Music",
+            Action::ToggleSfx => "Sounds",
+            Action::ToggleDebug => "Debug view",
+            Action::ToggleYetiControl => "Yeti control",
+            Action::DebugStun => "Stun",
+        }
+    }
+
+    pub fn default_key(&self) -> KeyCode {
+        match self {
+            Action::SteerLeft => KeyCode::A,
+            Action::SteerRight => KeyCode::D,
+            Action::Tuck => KeyCode::S,
+            Action::Brake => KeyCode::W,
+            Action::Pause => KeyCode::Escape,
+            Action::ToggleMusic => KeyCode::M,
+            Action::ToggleSfx => KeyCode::N,
+            Action::ToggleDebug => KeyCode::O,
+            Action::ToggleYetiControl => KeyCode::I,
+            Action::DebugStun => KeyCode::U,
+        }
+    }
+}
+
+/// Key bound to every action, actions missing from a saved map fall back to their default key.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    keys: HashMap<Action, KeyCode>,
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.keys.get(&action).copied().unwrap_or(action.default_key())
+    }
+
+    pub fn pressed(&self, keyboard_input: &Input<KeyCode>, action: Action) -> bool {
+        self.owns_key(action) && keyboard_input.pressed(self.key(action))
+    }
+
+    pub fn just_pressed(&self, keyboard_input: &Input<KeyCode>, action: Action) -> bool {
+        self.owns_key(action) && keyboard_input.just_pressed(self.key(action))
+    }
+
+    /// A hidden action whose key was taken on the controls screen stays quiet.
+    fn owns_key(&self, action: Action) -> bool {
+        self.action(self.key(action)) == Some(action)
+    }
+
+    /// Action bound to `key`, if any, the rebindable actions come first.
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| self.key(*action) == key)
+    }
+
+    /// Binds `key` to `action`, a rebindable action that already used `key` takes over the previous key of `action`.
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        let previous = self.key(action);
+        let other = Action::REBINDABLE.into_iter().find(|other| *other != action && self.key(*other) == key);
+        if let Some(other) = other {
+            self.keys.insert(other, previous);
+        }
+        self.keys.insert(action, key);
+    }
+
+    pub fn load(storage: &dyn Storage) -> Self {
+        load_ron(storage, KEY_BINDINGS_KEY)
+    }
+
+    pub fn save(&self, storage: &dyn Storage) {
+        save_ron(storage, KEY_BINDINGS_KEY, self);
+    }
+}
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+/// Action waiting for the next key press on the controls screen.
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+#[derive(Component)]
+struct ControlsElement;
+
+#[derive(Component)]
+struct RebindButton(Action);
+
+#[derive(Component)]
+struct ControlsBack;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameStorage>()
+            .init_resource::<Rebinding>()
+            .add_startup_system(load_key_bindings)
+            .add_system(setup_controls.in_schedule(OnEnter(GameState::Controls)))
+            .add_systems(
+                (
+                    rebind_interaction,
+                    rebind_input.after(rebind_interaction),
+                    update_rebind_buttons.after(rebind_input),
+                    back_interaction,
+                ).in_set(OnUpdate(GameState::Controls))
+            )
+            .add_system(despawn::<ControlsElement>.in_schedule(OnExit(GameState::Controls)));
+    }
+}
+
+fn load_key_bindings(
+    mut commands: Commands,
+    storage: Res<GameStorage>,
+) {
+    commands.insert_resource(KeyBindings::load(storage.0.as_ref()));
+}
+
+fn setup_controls(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    key_bindings: Res<KeyBindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    rebinding.0 = None;
+    let text_style = TextStyle {
+        font: game_resources.font_handle.clone(),
+        font_size: 16.0,
+        color: Color::BLACK,
+    };
+    let button_style = Style {
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        padding: UiRect {
+            left: Val::Px(12.0),
+            right: Val::Px(12.0),
+            top: Val::Px(4.0),
+            bottom: Val::Px(4.0)
+        },
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(40.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    gap: Size::height(Val::Px(8.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            ControlsElement,
+        ))
+        .with_children(|builder| {
+            for action in Action::REBINDABLE {
+                builder.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                }).with_children(|builder| {
+                    builder.spawn(
+                        TextBundle::from_section(action.name(), text_style.clone())
+                            .with_style(Style {
+                                margin: UiRect {
+                                    right: Val::Px(24.0),
+                                    ..default()
+                                },
+                                ..default()
+                            }),
+                    );
+                    builder.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::width(Val::Px(160.0)),
+                                ..button_style.clone()
+                            },
+                            background_color: NORMAL_BUTTON.into(),
+                            ..default()
+                        },
+                        RebindButton(action),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            key_name(key_bindings.key(action)),
+                            text_style.clone(),
+                        ));
+                    });
+                });
+            }
+        });
+
+    commands.spawn((
+        ButtonBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(400.0),
+                    ..default()
+                },
+                margin: UiRect {
+                    left: Val::Auto,
+                    right: Val::Auto,
+                    ..default()
+                },
+                padding: UiRect {
+                    left: Val::Px(12.0),
+                    right: Val::Px(12.0),
+                    top: Val::Px(8.0),
+                    bottom: Val::Px(8.0)
+                },
+                ..button_style
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        },
+        ControlsElement,
+        ControlsBack,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "Back",
+            TextStyle {
+                font_size: 24.0,
+                ..text_style
+            },
+        ));
+    });
+}
+
+fn rebind_interaction(
+    mut presses: ButtonPresses<RebindButton>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for button in presses.iter() {
+        rebinding.0 = Some(button.0);
+    }
+}
+
+fn rebind_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    storage: Res<GameStorage>,
+    mut key_bindings: ResMut<KeyBindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    let Some(key) = keyboard_input.get_just_pressed().next() else {
+        return;
+    };
+    // Esc backs out, Pause can still be moved onto it from its own row
+    if *key == KeyCode::Escape && action != Action::Pause {
+        rebinding.0 = None;
+        return;
+    }
+    key_bindings.bind(action, *key);
+    key_bindings.save(storage.0.as_ref());
+    rebinding.0 = None;
+}
+
+fn update_rebind_buttons(
+    key_bindings: Res<KeyBindings>,
+    rebinding: Res<Rebinding>,
+    mut button_q: Query<(&RebindButton, &mut BackgroundColor, &Children)>,
+    mut text_q: Query<&mut Text>,
+) {
+    if !key_bindings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (button, mut color, children) in button_q.iter_mut() {
+        let waiting = rebinding.0 == Some(button.0);
+        *color = if waiting { SELECTED_BUTTON.into() } else { NORMAL_BUTTON.into() };
+        for &child in children.iter() {
+            if let Ok(mut text) = text_q.get_mut(child) {
+                text.sections[0].value = if waiting {
+                    "press a key".to_string()
+                } else {
+                    key_name(key_bindings.key(button.0))
+                };
+            }
+        }
+    }
+}
+
+fn back_interaction(
+    mut presses: ButtonPresses<ControlsBack>,
+    mut rebinding: ResMut<Rebinding>,
+    mut app_state: ResMut<NextState<GameState>>,
+) {
+    if presses.iter().next().is_some() {
+        rebinding.0 = None;
+        app_state.set(GameState::MainMenu);
+    }
+}
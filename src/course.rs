@@ -0,0 +1,174 @@
+use bevy::{
+    prelude::*,
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    RaceSchedule,
+    GameResources,
+    player,
+    obstacle::{ObstacleType, spawn_obstacle},
+    posts::{PostColor, spawn_gate},
+    finish::spawn_finish,
+    level_generator::LevelGeneratorSettings,
+};
+
+pub const COURSES: [&str; 1] = [
+    "courses/first.course.ron",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseObstacle {
+    pub kind: ObstacleType,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseGate {
+    pub color: PostColor,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseFinish {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+}
+
+/// Hand-authored course. Positions are in world units, the whole course is shifted
+/// on spawn so that `start` ends up where the player is placed.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid)]
+#[uuid = "3c2f0c7e-5d0a-4a37-9a8e-6b1f2b4f7d21"]
+pub struct Course {
+    pub name: String,
+    pub start: (f32, f32),
+    pub obstacles: Vec<CourseObstacle>,
+    pub gates: Vec<CourseGate>,
+    pub finish: CourseFinish,
+}
+
+#[derive(Default)]
+pub struct CourseLoader;
+
+impl AssetLoader for CourseLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let course: Course = match load_context.path().extension().and_then(|ext| ext.to_str()) {
+                Some("json") => serde_json::from_slice(bytes)?,
+                _ => ron::de::from_bytes(bytes)?,
+            };
+            load_context.set_default_asset(LoadedAsset::new(course));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["course.ron", "course.json"]
+    }
+}
+
+#[derive(Resource)]
+pub struct CourseLibrary {
+    pub courses: Vec<Handle<Course>>,
+}
+
+#[derive(Resource)]
+pub struct SelectedCourse(pub Handle<Course>);
+
+/// Identifies what is being raced, either a generated course or a course asset by its path.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CourseId {
+    Seed(u64),
+    Course(String),
+}
+
+impl CourseId {
+    /// Seed for everything that is randomized during a race, stable for a given course.
+    pub fn seed(&self) -> u64 {
+        match self {
+            CourseId::Seed(seed) => *seed,
+            CourseId::Course(path) => path.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            }),
+        }
+    }
+}
+
+pub fn get_course_path(asset_server: &AssetServer, handle: &Handle<Course>) -> Option<String> {
+    asset_server.get_handle_path(handle)
+        .map(|asset_path| asset_path.path().to_string_lossy().to_string())
+}
+
+/// Inserts the resources needed to race `course_id` on the next `RaceSchedule::Setup`.
+pub fn select_course(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    course_id: &CourseId,
+) {
+    match course_id {
+        CourseId::Seed(seed) => {
+            commands.remove_resource::<SelectedCourse>();
+            commands.insert_resource(LevelGeneratorSettings::new(*seed));
+        },
+        CourseId::Course(path) => {
+            commands.remove_resource::<LevelGeneratorSettings>();
+            commands.insert_resource(SelectedCourse(asset_server.load(path.as_str())));
+        }
+    }
+    commands.insert_resource(course_id.clone());
+}
+
+pub struct CoursePlugin;
+
+impl Plugin for CoursePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<Course>()
+            .init_asset_loader::<CourseLoader>()
+            .add_startup_system(setup)
+            .add_system(spawn_course.after(player::setup).in_schedule(RaceSchedule::Setup));
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(CourseLibrary {
+        courses: COURSES.iter().map(|path| asset_server.load(*path)).collect(),
+    });
+}
+
+fn spawn_course(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    selected_course: Option<Res<SelectedCourse>>,
+    courses: Res<Assets<Course>>,
+) {
+    let Some(selected_course) = selected_course else {
+        return;
+    };
+    let Some(course) = courses.get(&selected_course.0) else {
+        warn!("Selected course is not loaded, nothing to spawn");
+        return;
+    };
+    let (start_x, start_y) = course.start;
+
+    for obstacle in course.obstacles.iter() {
+        spawn_obstacle(&mut commands, &game_resources, &obstacle.kind, obstacle.x - start_x, obstacle.y - start_y);
+    }
+    for gate in course.gates.iter() {
+        spawn_gate(&mut commands, &game_resources, &gate.color, gate.x - start_x, gate.y - start_y);
+    }
+    spawn_finish(&mut commands, course.finish.x - start_x, course.finish.y - start_y, course.finish.width);
+}
@@ -7,7 +7,7 @@ use crate::{
     yeti::{
         YetiSpawner,
         YetiSpawnPhase, Yeti
-    }, player::Player, GameResources, animation::AnimateRotation, SCALE_FACTOR, stuneffect::{StunEffect, Stun}
+    }, player::Player, GameResources, controls::{Action, KeyBindings}, animation::AnimateRotation, SCALE_FACTOR, stuneffect::{StunEffect, Stun}
 };
 
 #[derive(Component)]
@@ -28,7 +28,7 @@ impl Plugin for DebugPlugin {
                 is_debug_visible: false,
                 yeti_control: false
             })
-            .add_system(debug_input)
+            .add_system(debug_input.run_if(not(in_state(GameState::Controls))))
             .add_systems(
                 (
                     spawn_debug_yeti,
@@ -58,11 +58,12 @@ fn debug_input(
     mut commands: Commands,
     game_resources: Res<GameResources>,
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
     mut debug_controls: ResMut<DebugControls>,
     mut debug_q: Query<&mut Visibility, With<DebugMarker>>,
     stunnable_q: Query<Entity, Or<(With<Yeti>, With<Player>)>>
 ) {
-    if keyboard_input.just_pressed(KeyCode::O) {
+    if key_bindings.just_pressed(&keyboard_input, Action::ToggleDebug) {
         debug_controls.is_debug_visible = !debug_controls.is_debug_visible;
         info!("TOGGLE DEBUG {}", debug_controls.is_debug_visible);
 
@@ -74,12 +75,12 @@ fn debug_input(
         }
     }
 
-    if keyboard_input.just_pressed(KeyCode::I) {
+    if key_bindings.just_pressed(&keyboard_input, Action::ToggleYetiControl) {
         debug_controls.yeti_control = !debug_controls.yeti_control;
         info!("YETI CONTROL {}", debug_controls.yeti_control);
     }
 
-    if keyboard_input.just_pressed(KeyCode::U) {
+    if key_bindings.just_pressed(&keyboard_input, Action::DebugStun) {
         info!("ADDING STUN");
         for entity in stunnable_q.iter() {
             let stun_child = commands.spawn((
@@ -0,0 +1,511 @@
+use bevy::{prelude::*, math::vec2, input::mouse::{MouseWheel, MouseScrollUnit}, ecs::system::SystemParam};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::asset::FileAssetIo;
+
+use crate::{
+    GameState,
+    GameResources,
+    despawn,
+    SELECTED_BUTTON,
+    NORMAL_BUTTON,
+    collidable::Collidable,
+    debug::DebugMarker,
+    obstacle::{ObstacleType, spawn_obstacle},
+    posts::{PostColor, spawn_gate, POST_DISTANCE},
+    finish::{Finish, spawn_finish},
+    player::PLAYER_Z_INDEX,
+    course::{Course, CourseObstacle, CourseGate, CourseFinish, SelectedCourse, CourseLibrary},
+    spatialtree::SpatialTree,
+};
+
+const EDITOR_COURSE_ASSET: &str = "courses/editor.course.ron";
+const DEFAULT_FINISH: (f32, f32, f32) = (0.0, -2000.0, 2400.0);
+const SCROLL_SPEED: f32 = 600.0;
+const SCROLL_LINE_SIZE: f32 = 40.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum EditorTool {
+    Tree,
+    Stone,
+    Ramp,
+    BlueGate,
+    RedGate,
+    Finish,
+}
+
+#[derive(Clone, Component)]
+enum EditorButton {
+    Tool(EditorTool),
+    Save,
+    Menu,
+}
+
+#[derive(Component)]
+pub enum EditorItem {
+    Obstacle(ObstacleType),
+    Gate(PostColor),
+    Finish(f32),
+}
+
+impl EditorItem {
+    fn contains(&self, position: Vec2, point: Vec2, sprite_size: f32) -> bool {
+        let half_size = match self {
+            EditorItem::Obstacle(_) => vec2(sprite_size / 2.0, sprite_size / 2.0),
+            EditorItem::Gate(_) => vec2((POST_DISTANCE + sprite_size) / 2.0, sprite_size / 2.0),
+            EditorItem::Finish(width) => vec2(width / 2.0, sprite_size / 4.0),
+        };
+        let distance = (point - position).abs();
+        distance.x <= half_size.x && distance.y <= half_size.y
+    }
+}
+
+#[derive(Component)]
+struct EditorElement;
+
+type ToolbarQuery<'w, 's> = Query<'w, 's, (&'static Interaction, &'static EditorButton), (Changed<Interaction>, With<Button>)>;
+type ItemsQuery<'w, 's> = Query<'w, 's, (Entity, &'static EditorItem, &'static mut Transform, Option<&'static mut Collidable>, Option<&'static mut Finish>)>;
+
+/// Mouse position on the slope, unless it is over the toolbar.
+#[derive(SystemParam)]
+struct SlopeCursor<'w, 's> {
+    window: Query<'w, 's, &'static Window>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    buttons_q: Query<'w, 's, &'static Interaction, With<EditorButton>>,
+}
+
+impl SlopeCursor<'_, '_> {
+    fn position(&self) -> Option<Vec2> {
+        if self.buttons_q.iter().any(|interaction| *interaction != Interaction::None) {
+            return None;
+        }
+        let window = self.window.get_single().ok()?;
+        let (camera, camera_transform) = self.camera_q.get_single().ok()?;
+        camera.viewport_to_world_2d(camera_transform, window.cursor_position()?)
+    }
+}
+
+#[derive(Resource)]
+struct EditorState {
+    name: String,
+    tool: EditorTool,
+    dragging: Option<(Entity, Vec2)>,
+}
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(EditorState {
+                name: "Custom".to_string(),
+                tool: EditorTool::Tree,
+                dragging: None,
+            })
+            .add_systems(
+                (
+                    setup_ui,
+                    setup_course,
+                ).in_schedule(OnEnter(GameState::Editor))
+            )
+            .add_systems(
+                (
+                    toolbar_interaction,
+                    update_toolbar.after(toolbar_interaction),
+                    scroll_camera,
+                    mouse_interaction.after(toolbar_interaction),
+                    show_hitboxes,
+                ).in_set(OnUpdate(GameState::Editor))
+            )
+            .add_systems(
+                (
+                    despawn::<EditorItem>,
+                    despawn::<EditorElement>,
+                ).in_schedule(OnExit(GameState::Editor))
+            );
+    }
+}
+
+fn spawn_item(
+    commands: &mut Commands,
+    game_resources: &GameResources,
+    item: EditorItem,
+    x: f32,
+    y: f32,
+) -> Entity {
+    let entity = match &item {
+        EditorItem::Obstacle(obstacle_type) => spawn_obstacle(commands, game_resources, obstacle_type, x, y),
+        EditorItem::Gate(color) => spawn_gate(commands, game_resources, color, x, y),
+        EditorItem::Finish(width) => spawn_finish(commands, x, y, *width),
+    };
+    commands.entity(entity).insert(item);
+    entity
+}
+
+fn setup_course(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    mut editor_state: ResMut<EditorState>,
+    mut camera_q: Query<&mut Transform, With<Camera>>,
+    selected_course: Option<Res<SelectedCourse>>,
+    courses: Res<Assets<Course>>,
+) {
+    if let Ok(mut camera_transform) = camera_q.get_single_mut() {
+        camera_transform.translation.x = 0.0;
+        camera_transform.translation.y = 0.0;
+    }
+    editor_state.dragging = None;
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.5),
+                custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
+                rect: Some(game_resources.down),
+                ..default()
+            },
+            texture: game_resources.image_handle.clone(),
+            transform: Transform::from_xyz(0.0, 0.0, PLAYER_Z_INDEX),
+            ..default()
+        },
+        EditorElement,
+    ));
+
+    let course = selected_course.and_then(|selected_course| courses.get(&selected_course.0));
+    let Some(course) = course else {
+        editor_state.name = "Custom".to_string();
+        let (x, y, width) = DEFAULT_FINISH;
+        spawn_item(&mut commands, &game_resources, EditorItem::Finish(width), x, y);
+        return;
+    };
+
+    editor_state.name = course.name.clone();
+    let (start_x, start_y) = course.start;
+    for obstacle in course.obstacles.iter() {
+        spawn_item(&mut commands, &game_resources, EditorItem::Obstacle(obstacle.kind.clone()), obstacle.x - start_x, obstacle.y - start_y);
+    }
+    for gate in course.gates.iter() {
+        spawn_item(&mut commands, &game_resources, EditorItem::Gate(gate.color.clone()), gate.x - start_x, gate.y - start_y);
+    }
+    spawn_item(
+        &mut commands,
+        &game_resources,
+        EditorItem::Finish(course.finish.width),
+        course.finish.x - start_x,
+        course.finish.y - start_y
+    );
+}
+
+fn setup_ui(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    editor_state: Res<EditorState>,
+) {
+    let text_style = TextStyle {
+        font: game_resources.font_handle.clone(),
+        font_size: 16.0,
+        color: Color::BLACK,
+    };
+    let buttons = [
+        ("Tree", EditorButton::Tool(EditorTool::Tree)),
+        ("Stone", EditorButton::Tool(EditorTool::Stone)),
+        ("Ramp", EditorButton::Tool(EditorTool::Ramp)),
+        ("Blue", EditorButton::Tool(EditorTool::BlueGate)),
+        ("Red", EditorButton::Tool(EditorTool::RedGate)),
+        ("Finish", EditorButton::Tool(EditorTool::Finish)),
+        ("Save", EditorButton::Save),
+        ("Menu", EditorButton::Menu),
+    ];
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(4.0),
+                    top: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        },
+        EditorElement,
+    ))
+    .with_children(|builder| {
+        for (label, button) in buttons {
+            let selected = matches!(button, EditorButton::Tool(tool) if tool == editor_state.tool);
+            builder.spawn((
+                ButtonBundle {
+                    style: Style {
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect {
+                            right: Val::Px(4.0),
+                            ..default()
+                        },
+                        padding: UiRect {
+                            left: Val::Px(6.0),
+                            right: Val::Px(6.0),
+                            top: Val::Px(4.0),
+                            bottom: Val::Px(4.0)
+                        },
+                        ..default()
+                    },
+                    background_color: if selected { SELECTED_BUTTON.into() } else { NORMAL_BUTTON.into() },
+                    ..default()
+                },
+                button,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    label,
+                    text_style.clone(),
+                ));
+            });
+        }
+    });
+
+    commands.spawn((
+        TextBundle::from_section(
+            "LMB place/drag RMB delete W/S scroll",
+            text_style.clone(),
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(4.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        }),
+        EditorElement,
+    ));
+}
+
+fn toolbar_interaction(
+    interaction_query: ToolbarQuery,
+    mut app_state: ResMut<NextState<GameState>>,
+    mut editor_state: ResMut<EditorState>,
+    mut course_library: ResMut<CourseLibrary>,
+    asset_server: Res<AssetServer>,
+    items_q: Query<(&EditorItem, &Transform)>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            EditorButton::Tool(tool) => {
+                editor_state.tool = *tool;
+            },
+            EditorButton::Save => {
+                if !save_course(&editor_state.name, &asset_server, &items_q) {
+                    continue;
+                }
+                let handle = asset_server.load(EDITOR_COURSE_ASSET);
+                if course_library.courses.contains(&handle) {
+                    asset_server.reload_asset(EDITOR_COURSE_ASSET);
+                } else {
+                    course_library.courses.push(handle);
+                }
+            },
+            EditorButton::Menu => {
+                app_state.set(GameState::MainMenu);
+            }
+        }
+    }
+}
+
+fn update_toolbar(
+    editor_state: Res<EditorState>,
+    mut button_q: Query<(&mut BackgroundColor, &EditorButton)>,
+) {
+    if !editor_state.is_changed() {
+        return;
+    }
+    for (mut color, button) in button_q.iter_mut() {
+        *color = match button {
+            EditorButton::Tool(tool) if *tool == editor_state.tool => SELECTED_BUTTON.into(),
+            _ => NORMAL_BUTTON.into(),
+        };
+    }
+}
+
+fn scroll_camera(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ev_scroll: EventReader<MouseWheel>,
+    mut camera_q: Query<&mut Transform, With<Camera>>,
+) {
+    let Ok(mut camera_transform) = camera_q.get_single_mut() else {
+        return;
+    };
+    let mut dy = 0.0;
+    if keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up) {
+        dy += SCROLL_SPEED * time.delta_seconds();
+    }
+    if keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down) {
+        dy -= SCROLL_SPEED * time.delta_seconds();
+    }
+    for ev in ev_scroll.iter() {
+        dy += match ev.unit {
+            MouseScrollUnit::Line => ev.y * SCROLL_LINE_SIZE,
+            MouseScrollUnit::Pixel => ev.y,
+        };
+    }
+    camera_transform.translation.y += dy;
+}
+
+fn mouse_interaction(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    mouse_input: Res<Input<MouseButton>>,
+    slope_cursor: SlopeCursor,
+    mut editor_state: ResMut<EditorState>,
+    mut items_q: ItemsQuery,
+    mut spatial_tree: ResMut<SpatialTree>,
+) {
+    if mouse_input.just_released(MouseButton::Left) {
+        editor_state.dragging = None;
+    }
+    let Some(cursor) = slope_cursor.position() else {
+        return;
+    };
+
+    let hovered = items_q.iter()
+        .filter(|(_, item, transform, _, _)| item.contains(transform.translation.truncate(), cursor, game_resources.sprite_size))
+        .max_by(|(_, _, a, _, _), (_, _, b, _, _)| a.translation.z.total_cmp(&b.translation.z))
+        .map(|(entity, _, transform, _, _)| (entity, transform.translation.truncate()));
+
+    if mouse_input.just_pressed(MouseButton::Right) {
+        if let Some((entity, _)) = hovered {
+            if !matches!(items_q.get(entity), Ok((_, EditorItem::Finish(_), _, _, _))) {
+                spatial_tree.remove(entity);
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        return;
+    }
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        if let Some((entity, position)) = hovered {
+            editor_state.dragging = Some((entity, position - cursor));
+            return;
+        }
+        let item = match editor_state.tool {
+            EditorTool::Tree => EditorItem::Obstacle(ObstacleType::Tree),
+            EditorTool::Stone => EditorItem::Obstacle(ObstacleType::Stone),
+            EditorTool::Ramp => EditorItem::Obstacle(ObstacleType::Ramp),
+            EditorTool::BlueGate => EditorItem::Gate(PostColor::Blue),
+            EditorTool::RedGate => EditorItem::Gate(PostColor::Red),
+            EditorTool::Finish => {
+                for (entity, item, _, _, _) in items_q.iter() {
+                    if let EditorItem::Finish(_) = item {
+                        editor_state.dragging = Some((entity, Vec2::ZERO));
+                    }
+                }
+                return;
+            }
+        };
+        spawn_item(&mut commands, &game_resources, item, cursor.x, cursor.y);
+        return;
+    }
+
+    let Some((entity, offset)) = editor_state.dragging else {
+        return;
+    };
+    if !mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+    let Ok((_, item, mut transform, collidable, finish)) = items_q.get_mut(entity) else {
+        editor_state.dragging = None;
+        return;
+    };
+    let position = cursor + offset;
+    transform.translation.y = position.y;
+    if let EditorItem::Finish(_) = item {
+        if let Some(mut finish) = finish {
+            finish.0 = position.y;
+        }
+    } else {
+        transform.translation.x = position.x;
+    }
+    if let Some(mut collidable) = collidable {
+        collidable.update_center(transform.translation.x, transform.translation.y);
+    }
+}
+
+fn show_hitboxes(
+    mut debug_q: Query<&mut Visibility, Added<DebugMarker>>,
+) {
+    for mut visibility in debug_q.iter_mut() {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+fn save_course(
+    name: &str,
+    asset_server: &AssetServer,
+    items_q: &Query<(&EditorItem, &Transform)>,
+) -> bool {
+    let mut obstacles = vec![];
+    let mut gates = vec![];
+    let (x, y, width) = DEFAULT_FINISH;
+    let mut finish = CourseFinish { x, y, width };
+
+    for (item, transform) in items_q.iter() {
+        let (x, y) = (transform.translation.x, transform.translation.y);
+        match item {
+            EditorItem::Obstacle(kind) => obstacles.push(CourseObstacle { kind: kind.clone(), x, y }),
+            EditorItem::Gate(color) => gates.push(CourseGate { color: color.clone(), x, y }),
+            EditorItem::Finish(width) => finish = CourseFinish { x, y, width: *width },
+        }
+    }
+    obstacles.sort_by(|a, b| b.y.total_cmp(&a.y));
+    gates.sort_by(|a, b| b.y.total_cmp(&a.y));
+
+    let course = Course {
+        name: name.to_string(),
+        start: (0.0, 0.0),
+        obstacles,
+        gates,
+        finish,
+    };
+    let serialized = match ron::ser::to_string_pretty(&course, ron::ser::PrettyConfig::default()) {
+        Ok(serialized) => serialized,
+        Err(err) => {
+            error!("Unable to serialize course: {}", err);
+            return false;
+        }
+    };
+    write_course(asset_server, &serialized)
+}
+
+/// Writes into the folder the asset server loads from, not the working directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_course(asset_server: &AssetServer, serialized: &str) -> bool {
+    let Some(asset_io) = asset_server.asset_io().downcast_ref::<FileAssetIo>() else {
+        error!("Courses can only be saved next to file system assets");
+        return false;
+    };
+    let path = asset_io.root_path().join(EDITOR_COURSE_ASSET);
+    match std::fs::write(&path, serialized) {
+        Ok(_) => {
+            info!("Course saved to {}", path.display());
+            true
+        },
+        Err(err) => {
+            error!("Unable to save course to {}: {}", path.display(), err);
+            false
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_course(_asset_server: &AssetServer, serialized: &str) -> bool {
+    info!("Saving courses is not supported on web, course data:\n{}", serialized);
+    false
+}
@@ -1,44 +1,70 @@
 use bevy::{prelude::*};
 
 use crate::{
-    GameState,
+    RaceSchedule,
     player::{
-        Player, CompletedRace, Slowdown,
+        Player, CompletedRace, Disqualified, Slowdown, RaceClock, update_movables,
     },
     despawn,
+    simulation::SimulationSet,
 };
 
 
 #[derive(Component)]
 pub struct Finish(pub f32);
 
+/// Still racing, a disqualified player can slide over the line but does not finish.
+type RacingPlayer = (With<Player>, Without<Slowdown>, Without<CompletedRace>, Without<Disqualified>);
+
 pub struct FinishPlugin;
 
 impl Plugin for FinishPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_system(despawn::<Finish>.in_schedule(OnExit(GameState::GameOver)))
+            .add_system(despawn::<Finish>.in_schedule(RaceSchedule::Teardown))
             .add_systems(
                 (
-                    check_finish_crossed,
-                ).in_set(OnUpdate(GameState::Playing))
+                    check_finish_crossed.after(update_movables),
+                ).in_set(SimulationSet::Tick).in_schedule(CoreSchedule::FixedUpdate)
             );
     }
 }
 
+pub fn spawn_finish(
+    commands: &mut Commands,
+    x: f32,
+    y: f32,
+    width: f32,
+) -> Entity {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK,
+                custom_size: Some(Vec2::new(width, 8.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(x, y, 0.0),
+            ..default()
+        },
+        Finish(y),
+    ))
+    .id()
+}
+
 fn check_finish_crossed(
     mut commands: Commands,
     finish_q: Query<&Finish>,
-    player_q: Query<(Entity, &Transform), (With<Player>, Without<Slowdown>, Without<CompletedRace>)>,
+    mut player_q: Query<(Entity, &Transform, &mut RaceClock), RacingPlayer>,
 ) {
     let Ok(finish) = finish_q.get_single() else {
         return;
     };
-    let Ok((entity, transform)) = player_q.get_single() else {
+    let Ok((entity, transform, mut clock)) = player_q.get_single_mut() else {
         return;
     };
 
     if transform.translation.y < finish.0 {
+        clock.stop();
         commands.entity(entity).insert(Slowdown(Timer::from_seconds(0.2, TimerMode::Once)));
         commands.entity(entity).insert(CompletedRace);
     }
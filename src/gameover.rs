@@ -1,14 +1,17 @@
 use bevy::{prelude::*};
 
-use crate::{GameState, despawn, GameResources, NORMAL_BUTTON, player::{CompletedRace, Player}};
+use crate::{GameState, despawn, GameResources, NORMAL_BUTTON, player::{CompletedRace, Disqualified, Player, RaceClock, Score, format_result}, posts::GateResults, replay::{LastReplay, start_replay}, gamepad::ButtonPresses};
 
 #[derive(Component)]
 struct GameOverElement;
 
+type RaceResultQuery<'w, 's> = Query<'w, 's, (&'static RaceClock, &'static Score, Option<&'static CompletedRace>, Option<&'static Disqualified>), With<Player>>;
+
 #[derive(Component)]
 enum GameOverControl {
     Restart,
-    MainMenu
+    MainMenu,
+    Replay,
 }
 
 pub struct GameOverPlugin;
@@ -23,25 +26,25 @@ impl Plugin for GameOverPlugin {
 }
 
 fn controls_interaction(
-    interaction_query: Query<
-        (&Interaction, &GameOverControl),
-        (Changed<Interaction>, With<Button>, With<GameOverElement>),
-    >,
+    mut presses: ButtonPresses<GameOverControl>,
+    mut commands: Commands,
     mut app_state: ResMut<NextState<GameState>>,
+    asset_server: Res<AssetServer>,
+    last_replay: Option<Res<LastReplay>>,
 ) {
-    for (interaction, control) in &interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                match control {
-                    GameOverControl::MainMenu => {
-                        app_state.set(GameState::MainMenu);
-                    },
-                    GameOverControl::Restart => {
-                        app_state.set(GameState::Playing);
-                    }
-                }
+    for control in presses.iter() {
+        match control {
+            GameOverControl::MainMenu => {
+                app_state.set(GameState::MainMenu);
             },
-            _ => {}
+            GameOverControl::Restart => {
+                app_state.set(GameState::Playing);
+            },
+            GameOverControl::Replay => {
+                if let Some(last_replay) = &last_replay {
+                    start_replay(&mut commands, &asset_server, &mut app_state, &last_replay.0);
+                }
+            }
         }
     }
 }
@@ -49,7 +52,8 @@ fn controls_interaction(
 fn setup_gameover(
     mut commands: Commands,
     game_resources: Res<GameResources>,
-    player_q: Query<Option<&CompletedRace>, With<Player>>,
+    gate_results: Res<GateResults>,
+    player_q: RaceResultQuery,
 ) {
     let text_style = TextStyle {
         font: game_resources.font_handle.clone(),
@@ -58,9 +62,13 @@ fn setup_gameover(
     };
     let text_alignment = TextAlignment::Center;
     let mut text = "Game Over";
-    if let Ok(completed) = player_q.get_single() {
-        if completed.is_some() {
-            text = "Race finished !"
+    let mut result = String::new();
+    if let Ok((clock, score, completed, disqualified)) = player_q.get_single() {
+        result = format_result(clock, score);
+        if disqualified.is_some() {
+            text = "Disqualified";
+        } else if completed.is_some() {
+            text = "Race finished !";
         }
     }
 
@@ -70,6 +78,28 @@ fn setup_gameover(
             text_style.clone(),
         )
         .with_text_alignment(text_alignment)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(175.0),
+                ..default()
+            },
+            margin: UiRect {
+                left: Val::Auto,
+                right: Val::Auto,
+                ..default()
+            },
+            ..default()
+        }),
+        GameOverElement,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            result,
+            text_style.clone(),
+        )
+        .with_text_alignment(text_alignment)
         .with_style(Style {
             position_type: PositionType::Absolute,
             position: UiRect {
@@ -86,6 +116,28 @@ fn setup_gameover(
         GameOverElement,
     ));
 
+    commands.spawn((
+        TextBundle::from_section(
+            format!("Gates passed: {}  missed: {}", gate_results.passed, gate_results.missed),
+            text_style.clone(),
+        )
+        .with_text_alignment(text_alignment)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(225.0),
+                ..default()
+            },
+            margin: UiRect {
+                left: Val::Auto,
+                right: Val::Auto,
+                ..default()
+            },
+            ..default()
+        }),
+        GameOverElement,
+    ));
+
     commands.spawn((
         ButtonBundle {
             style: Style {
@@ -157,4 +209,40 @@ fn setup_gameover(
             text_style.clone(),
         ));
     });
+
+    commands.spawn((
+        ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(400.0),
+                    ..default()
+                },
+                margin: UiRect {
+                    left: Val::Auto,
+                    right: Val::Auto,
+                    ..default()
+                },
+                padding: UiRect {
+                    left: Val::Px(12.0),
+                    right: Val::Px(12.0),
+                    top: Val::Px(8.0),
+                    bottom: Val::Px(8.0)
+                },
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        },
+        GameOverElement,
+        GameOverControl::Replay,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "Replay",
+            text_style.clone(),
+        ));
+    });
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use bevy::{prelude::*, ecs::system::SystemParam};
+
+use crate::{GameState, FOCUSED_TEXT};
+
+/// Button picked with the d-pad.
+#[derive(Resource, Default)]
+struct GamepadFocus {
+    focused: Option<Entity>,
+}
+
+/// The face button pressed the focused button, menus handle it like a click.
+pub struct ButtonActivated(pub Entity);
+
+/// Buttons with a `T` that were clicked or activated with the gamepad this frame.
+#[derive(SystemParam)]
+pub struct ButtonPresses<'w, 's, T: Component> {
+    interaction_q: Query<'w, 's, (&'static Interaction, &'static T), (Changed<Interaction>, With<Button>)>,
+    button_q: Query<'w, 's, &'static T, With<Button>>,
+    ev_activated: EventReader<'w, 's, ButtonActivated>,
+}
+
+impl<'w, 's, T: Component> ButtonPresses<'w, 's, T> {
+    pub fn iter(&mut self) -> impl Iterator<Item = &T> {
+        let clicked = self.interaction_q.iter()
+            .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+            .map(|(_, button)| button);
+        let activated = self.ev_activated.iter()
+            .filter_map(|ButtonActivated(entity)| self.button_q.get(*entity).ok());
+        clicked.chain(activated)
+    }
+}
+
+pub struct GamepadNavigationPlugin;
+
+impl Plugin for GamepadNavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GamepadFocus>()
+            .add_event::<ButtonActivated>()
+            .add_system(
+                navigate_buttons.run_if(
+                    in_state(GameState::MainMenu)
+                        .or_else(in_state(GameState::GameOver))
+                        .or_else(in_state(GameState::Leaderboard))
+                        .or_else(in_state(GameState::Controls))
+                        .or_else(in_state(GameState::Paused))
+                        .or_else(in_state(GameState::Settings))
+                )
+            );
+    }
+}
+
+/// The d-pad walks the buttons in reading order, South activates the focused one and East goes back to the first one.
+fn navigate_buttons(
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut focus: ResMut<GamepadFocus>,
+    mut ev_activated: EventWriter<ButtonActivated>,
+    button_q: Query<(Entity, &GlobalTransform, &Children), With<Button>>,
+    mut text_q: Query<&mut Text>,
+) {
+    let mut buttons: Vec<(Entity, Vec3)> = button_q.iter()
+        .map(|(entity, transform, _)| (entity, transform.translation()))
+        .collect();
+    if buttons.is_empty() {
+        return;
+    }
+    // ui nodes are laid out from the top left corner
+    buttons.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+    let just_pressed = |button_type| gamepads.iter()
+        .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)));
+    let step: isize = if just_pressed(GamepadButtonType::DPadDown) || just_pressed(GamepadButtonType::DPadRight) {
+        1
+    } else if just_pressed(GamepadButtonType::DPadUp) || just_pressed(GamepadButtonType::DPadLeft) {
+        -1
+    } else {
+        0
+    };
+
+    let current = focus.focused.and_then(|focused| buttons.iter().position(|(entity, _)| *entity == focused));
+    let next = match current {
+        _ if just_pressed(GamepadButtonType::East) => Some(0),
+        Some(index) => Some((index as isize + step).rem_euclid(buttons.len() as isize) as usize),
+        None if step != 0 => Some(0),
+        None => None,
+    };
+    let Some(next) = next else {
+        return;
+    };
+    let next = buttons[next].0;
+
+    if focus.focused != Some(next) {
+        for (entity, color) in [(focus.focused, Color::BLACK), (Some(next), FOCUSED_TEXT)] {
+            let Some(Ok((_, _, children))) = entity.map(|entity| button_q.get(entity)) else {
+                continue;
+            };
+            for &child in children.iter() {
+                if let Ok(mut text) = text_q.get_mut(child) {
+                    for section in text.sections.iter_mut() {
+                        section.style.color = color;
+                    }
+                }
+            }
+        }
+        focus.focused = Some(next);
+    }
+
+    if just_pressed(GamepadButtonType::South) {
+        ev_activated.send(ButtonActivated(next));
+    }
+}
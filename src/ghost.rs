@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, math::vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    RaceSchedule,
+    GameResources,
+    despawn,
+    player::{self, Player, Rotation, CompletedRace, get_graphics, PLAYER_Z_INDEX},
+    splits::{self, NewPersonalBest},
+    course::CourseId,
+    simulation::{SimulationSet, Interpolated},
+    storage::{GameStorage, Storage, load_ron, save_ron},
+};
+
+const GHOST_SAMPLE_INTERVAL: f32 = 1.0 / 20.0;
+const GHOST_ALPHA: f32 = 0.4;
+const GHOSTS_KEY: &str = "ghosts";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GhostSample {
+    pub time: f32,
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+}
+
+/// Trajectory of a completed run, `time` is the time it took to finish.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GhostRun {
+    pub time: f32,
+    pub samples: Vec<GhostSample>,
+}
+
+impl GhostRun {
+    fn sample_at(&self, time: f32) -> Option<GhostSample> {
+        let next_index = self.samples.iter().position(|sample| sample.time >= time)?;
+        let next = &self.samples[next_index];
+        if next_index == 0 {
+            return Some(next.clone());
+        }
+        let previous = &self.samples[next_index - 1];
+        let t = ((time - previous.time) / (next.time - previous.time).max(f32::EPSILON)).clamp(0.0, 1.0);
+        Some(GhostSample {
+            time,
+            x: previous.x + (next.x - previous.x) * t,
+            y: previous.y + (next.y - previous.y) * t,
+            rotation: previous.rotation + (next.rotation - previous.rotation) * t,
+        })
+    }
+}
+
+/// Personal best run for every course that was finished.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct GhostLibrary {
+    runs: HashMap<CourseId, GhostRun>,
+}
+
+impl GhostLibrary {
+    pub fn get(&self, course_id: &CourseId) -> Option<&GhostRun> {
+        self.runs.get(course_id)
+    }
+
+    pub fn load(storage: &dyn Storage) -> Self {
+        load_ron(storage, GHOSTS_KEY)
+    }
+
+    pub fn save(&self, storage: &dyn Storage) {
+        save_ron(storage, GHOSTS_KEY, self);
+    }
+}
+
+/// The player until the finish line, the ghost is not recorded further.
+type RacingPlayer = (With<Player>, Without<CompletedRace>);
+
+#[derive(Resource)]
+struct GhostRecorder {
+    elapsed: f32,
+    timer: Timer,
+    samples: Vec<GhostSample>,
+}
+
+#[derive(Component)]
+pub struct Ghost {
+    run: GhostRun,
+    elapsed: f32,
+}
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameStorage>()
+            .add_startup_system(load_ghosts)
+            .insert_resource(GhostRecorder {
+                elapsed: 0.0,
+                timer: Timer::from_seconds(GHOST_SAMPLE_INTERVAL, TimerMode::Repeating),
+                samples: vec![],
+            })
+            .add_systems(
+                (
+                    reset_recorder,
+                    spawn_ghost,
+                ).in_schedule(RaceSchedule::Setup)
+            )
+            .add_systems(
+                (
+                    record_ghost.after(player::update_movables),
+                    update_ghost,
+                ).in_set(SimulationSet::Tick).in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_system(store_ghost.after(splits::store_personal_best).in_schedule(OnEnter(GameState::GameOver)))
+            .add_system(despawn::<Ghost>.in_schedule(RaceSchedule::Teardown));
+    }
+}
+
+fn load_ghosts(
+    mut commands: Commands,
+    storage: Res<GameStorage>,
+) {
+    commands.insert_resource(GhostLibrary::load(storage.0.as_ref()));
+}
+
+fn reset_recorder(
+    mut recorder: ResMut<GhostRecorder>,
+) {
+    recorder.elapsed = 0.0;
+    recorder.timer.reset();
+    recorder.samples.clear();
+}
+
+fn record_ghost(
+    fixed_time: Res<FixedTime>,
+    mut recorder: ResMut<GhostRecorder>,
+    player_q: Query<(&Transform, &Rotation), RacingPlayer>,
+) {
+    let Ok((transform, rotation)) = player_q.get_single() else {
+        return;
+    };
+    recorder.elapsed += fixed_time.period.as_secs_f32();
+    if !recorder.samples.is_empty() && !recorder.timer.tick(fixed_time.period).just_finished() {
+        return;
+    }
+    let sample = GhostSample {
+        time: recorder.elapsed,
+        x: transform.translation.x,
+        y: transform.translation.y,
+        rotation: rotation.0,
+    };
+    recorder.samples.push(sample);
+}
+
+/// The ghost follows the personal best kept by the splits, so both agree on which run was the best.
+fn store_ghost(
+    mut recorder: ResMut<GhostRecorder>,
+    mut ghost_library: ResMut<GhostLibrary>,
+    storage: Res<GameStorage>,
+    course_id: Option<Res<CourseId>>,
+    mut ev_personal_best: EventReader<NewPersonalBest>,
+) {
+    let Some(time) = ev_personal_best.iter().last().map(|personal_best| personal_best.time) else {
+        return;
+    };
+    let Some(course_id) = course_id else {
+        return;
+    };
+    let samples = std::mem::take(&mut recorder.samples);
+    ghost_library.runs.insert(course_id.clone(), GhostRun { time, samples });
+    ghost_library.save(storage.0.as_ref());
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    ghost_library: Res<GhostLibrary>,
+    course_id: Option<Res<CourseId>>,
+) {
+    let Some(run) = course_id.and_then(|course_id| ghost_library.runs.get(&course_id).cloned()) else {
+        return;
+    };
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
+                rect: Some(game_resources.down),
+                ..default()
+            },
+            texture: game_resources.image_handle.clone(),
+            transform: Transform::from_xyz(0., 0., PLAYER_Z_INDEX - 0.1),
+            ..default()
+        },
+        Interpolated::new(Vec2::ZERO),
+        Ghost {
+            run,
+            elapsed: 0.0,
+        },
+    ));
+}
+
+fn update_ghost(
+    fixed_time: Res<FixedTime>,
+    game_resources: Res<GameResources>,
+    mut ghost_q: Query<(&mut Transform, &mut Sprite, &mut Ghost)>,
+) {
+    for (mut transform, mut sprite, mut ghost) in ghost_q.iter_mut() {
+        ghost.elapsed += fixed_time.period.as_secs_f32();
+        let Some(sample) = ghost.run.sample_at(ghost.elapsed) else {
+            continue;
+        };
+        transform.translation.x = sample.x;
+        transform.translation.y = sample.y;
+        let (sprite_rect, flip_x) = get_graphics(sample.rotation, &game_resources);
+        sprite.rect = Some(sprite_rect);
+        sprite.flip_x = flip_x;
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    ecs::system::SystemState,
+    input::{InputPlugin, gamepad::{GamepadAxisChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadEvent, GamepadInfo}},
+    time::TimeUpdateStrategy,
+    utils::Instant,
+};
+
+use crate::{
+    GameState,
+    GameResources,
+    GameplayPlugin,
+    player::Player,
+    controls::{Action, KeyBindings},
+    pause::PauseTransition,
+    uicontrols::{UiControlType, SkiStance},
+    simulation::TICKS_PER_SECOND,
+};
+
+/// Gameplay without a window, renderer or audio, every `tick` advances the simulation by exactly one fixed step.
+pub struct HeadlessApp {
+    pub app: App,
+    instant: Instant,
+}
+
+impl HeadlessApp {
+    pub fn new() -> Self {
+        let mut app = App::new();
+        let instant = Instant::now();
+        app
+            .add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualInstant(instant))
+            .add_plugin(InputPlugin)
+            .insert_resource(GameResources::new(Handle::default(), Handle::default()))
+            .add_plugin(GameplayPlugin);
+        app.world.spawn(Camera2dBundle::default());
+        Self { app, instant }
+    }
+
+    /// Switches to `GameState::Playing` and runs the frame that spawns the player.
+    pub fn start_race(&mut self) {
+        self.app.world.resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        self.tick();
+    }
+
+    /// Pauses or resumes the race the way the pause menu does and runs the frame that switches the state.
+    pub fn toggle_pause(&mut self) {
+        let next = match self.state() {
+            GameState::Paused => GameState::Playing,
+            _ => GameState::Paused,
+        };
+        self.app.insert_resource(PauseTransition);
+        self.app.world.resource_mut::<NextState<GameState>>().set(next);
+        self.tick();
+    }
+
+    pub fn tick(&mut self) {
+        self.advance(1);
+    }
+
+    /// Runs a single frame that lasts `ticks` fixed steps, like a slower display would.
+    pub fn advance(&mut self, ticks: u32) {
+        self.instant += Duration::from_secs_f32(1.0 / TICKS_PER_SECOND) * ticks;
+        self.app.insert_resource(TimeUpdateStrategy::ManualInstant(self.instant));
+        self.app.update();
+    }
+
+    /// Runs ticks until `predicate` holds, returns false when it did not within `max_ticks`.
+    pub fn run_until(&mut self, max_ticks: usize, predicate: impl Fn(&mut Self) -> bool) -> bool {
+        for _ in 0..max_ticks {
+            if predicate(self) {
+                return true;
+            }
+            self.tick();
+        }
+        predicate(self)
+    }
+
+    /// Scripted input, holds the bound steering key until it is changed.
+    pub fn steer(&mut self, control_type: Option<UiControlType>) {
+        let action = control_type.map(|control_type| match control_type {
+            UiControlType::Left => Action::SteerLeft,
+            UiControlType::Right => Action::SteerRight,
+        });
+        self.hold(&[Action::SteerLeft, Action::SteerRight], action);
+    }
+
+    /// Holds the bound tuck or brake key until it is changed.
+    pub fn stance(&mut self, stance: SkiStance) {
+        let action = match stance {
+            SkiStance::Tuck => Some(Action::Tuck),
+            SkiStance::Brake => Some(Action::Brake),
+            SkiStance::Normal => None,
+        };
+        self.hold(&[Action::Tuck, Action::Brake], action);
+    }
+
+    fn hold(&mut self, actions: &[Action], action: Option<Action>) {
+        let key_bindings = self.app.world.resource::<KeyBindings>().clone();
+        let mut keyboard_input = self.app.world.resource_mut::<Input<KeyCode>>();
+        for other in actions {
+            keyboard_input.release(key_bindings.key(*other));
+        }
+        if let Some(action) = action {
+            keyboard_input.press(key_bindings.key(action));
+        }
+    }
+
+    /// Tilts the left stick of a gamepad, connecting it first, `x` goes from -1 (left) to 1 (right).
+    pub fn tilt_stick(&mut self, x: f32) {
+        let gamepad = Gamepad::new(0);
+        if !self.app.world.resource::<Gamepads>().contains(gamepad) {
+            let info = GamepadInfo { name: "Headless gamepad".to_string() };
+            self.app.world.send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(gamepad, GamepadConnection::Connected(info))));
+        }
+        self.app.world.send_event(GamepadEvent::Axis(GamepadAxisChangedEvent::new(gamepad, GamepadAxisType::LeftStickX, x)));
+    }
+
+    /// Spawns through the same helpers the game uses, e.g. `obstacle::spawn_obstacle`.
+    pub fn spawn(&mut self, spawn: impl FnOnce(&mut Commands, &GameResources) -> Entity) -> Entity {
+        let mut system_state: SystemState<(Commands, Res<GameResources>)> = SystemState::new(&mut self.app.world);
+        let (mut commands, game_resources) = system_state.get_mut(&mut self.app.world);
+        let entity = spawn(&mut commands, &game_resources);
+        system_state.apply(&mut self.app.world);
+        entity
+    }
+
+    pub fn player(&mut self) -> Option<Entity> {
+        self.app.world.query_filtered::<Entity, With<Player>>().get_single(&self.app.world).ok()
+    }
+
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.app.world.get::<T>(entity).is_some()
+    }
+
+    pub fn state(&self) -> GameState {
+        self.app.world.resource::<State<GameState>>().0
+    }
+}
+
+impl Default for HeadlessApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,185 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_8};
+
+use bevy::{prelude::*};
+
+use crate::{
+    GameState,
+    GameResources,
+    SCALE_FACTOR,
+    Alive,
+    collidable::Collidable,
+    obstacle::{Obstacle, self},
+    player::{Player, Rotation, Velocity, SkisQuery, fall_down, PLAYER_Z_INDEX, self},
+    simulation::SimulationSet,
+};
+
+pub const RAMP_DIMENSIONS: (f32, f32) = (4.0 * SCALE_FACTOR, 2.0 * SCALE_FACTOR);
+const RAMP_COLOR: Color = Color::rgb(0.82, 0.88, 0.97);
+const RAMP_LIP_COLOR: Color = Color::rgb(0.55, 0.65, 0.8);
+const SHADOW_COLOR: Color = Color::rgba(0.3, 0.3, 0.4, 0.4);
+const FLIGHT_TIME_PER_SPEED: f32 = 0.8 / (50.0 * SCALE_FACTOR);
+const FLIGHT_TIME_RANGE: (f32, f32) = (0.3, 1.2);
+const PEAK_HEIGHT_PER_SECOND: f32 = 12.0 * SCALE_FACTOR;
+const SCALE_PER_HEIGHT: f32 = 0.3 / (10.0 * SCALE_FACTOR);
+/// Largest difference between the ski direction and the flight direction the skier can land with.
+const LANDING_TOLERANCE: f32 = FRAC_PI_8 * 1.5;
+
+#[derive(Component)]
+pub struct Ramp;
+
+/// Ballistic flight after a ramp, the skier keeps the launch velocity until the flight is over.
+#[derive(Component)]
+pub struct Airborne {
+    flight: Timer,
+    peak: f32,
+}
+
+impl Airborne {
+    pub fn new(speed: f32) -> Self {
+        let flight_time = (speed * FLIGHT_TIME_PER_SPEED).clamp(FLIGHT_TIME_RANGE.0, FLIGHT_TIME_RANGE.1);
+        Self {
+            flight: Timer::from_seconds(flight_time, TimerMode::Once),
+            peak: flight_time * PEAK_HEIGHT_PER_SECOND,
+        }
+    }
+
+    pub fn height(&self) -> f32 {
+        let t = self.flight.percent();
+        4.0 * self.peak * t * (1.0 - t)
+    }
+}
+
+#[derive(Component)]
+struct JumpShadow;
+
+type AirborneQuery<'w, 's> = Query<'w, 's, (Entity, &'static mut Airborne, &'static Rotation, &'static Velocity, &'static mut Sprite, &'static Children), With<Player>>;
+type SkierScaleQuery<'w, 's> = Query<'w, 's, (&'static mut Transform, Option<&'static Airborne>, &'static Children), (With<Player>, Without<JumpShadow>)>;
+
+pub struct JumpPlugin;
+
+impl Plugin for JumpPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(
+                update_airborne
+                    .after(player::update_movables)
+                    .before(obstacle::process_collisions_player)
+                    .in_set(SimulationSet::Tick)
+                    .in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_systems(
+                (
+                    add_shadow,
+                    update_airborne_graphics,
+                ).in_set(OnUpdate(GameState::Playing))
+            );
+    }
+}
+
+pub fn spawn_ramp(
+    commands: &mut Commands,
+    x: f32,
+    y: f32,
+) -> Entity {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: RAMP_COLOR,
+                custom_size: Some(Vec2::new(RAMP_DIMENSIONS.0 * 2.0, RAMP_DIMENSIONS.1 * 2.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(x, y, PLAYER_Z_INDEX - 0.5),
+            ..default()
+        },
+        Collidable::new(x, y, RAMP_DIMENSIONS.0, RAMP_DIMENSIONS.1, 0.0, 0.0),
+        Alive,
+        Obstacle,
+        Ramp,
+    ))
+    .with_children(|parent| {
+        parent.spawn(SpriteBundle {
+            sprite: Sprite {
+                color: RAMP_LIP_COLOR,
+                custom_size: Some(Vec2::new(RAMP_DIMENSIONS.0 * 2.0, SCALE_FACTOR)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, -RAMP_DIMENSIONS.1, 0.1),
+            ..default()
+        });
+    })
+    .id()
+}
+
+/// Lands the skier once the flight is over, a landing with the skis across the flight direction falls.
+fn update_airborne(
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    game_resources: Res<GameResources>,
+    mut player_q: AirborneQuery,
+    shadows_q: Query<Entity, With<JumpShadow>>,
+    mut skis_q: SkisQuery,
+) {
+    let Ok((entity, mut airborne, rotation, velocity, mut sprite, children)) = player_q.get_single_mut() else {
+        return;
+    };
+    if !airborne.flight.tick(fixed_time.period).finished() {
+        return;
+    }
+
+    commands.entity(entity).remove::<Airborne>();
+    for &child in children {
+        if shadows_q.contains(child) {
+            commands.entity(child).despawn_recursive();
+        }
+    }
+    let act_rotation = rotation.0 - FRAC_PI_2;
+    let heading = Vec2::new(act_rotation.cos(), act_rotation.sin());
+    if velocity.0 != Vec2::ZERO && heading.angle_between(velocity.0).abs() > LANDING_TOLERANCE {
+        fall_down(&mut commands, &game_resources, entity, &mut sprite, children, &mut skis_q);
+    }
+}
+
+fn add_shadow(
+    mut commands: Commands,
+    player_q: Query<Entity, Added<Airborne>>,
+) {
+    for entity in player_q.iter() {
+        let shadow = commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: SHADOW_COLOR,
+                    custom_size: Some(Vec2::new(4.0 * SCALE_FACTOR, 2.0 * SCALE_FACTOR)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, -1.0),
+                ..default()
+            },
+            JumpShadow,
+        )).id();
+        commands.entity(entity).add_child(shadow);
+    }
+}
+
+/// Height is shown by scaling the skier up and dropping the shadow below.
+fn update_airborne_graphics(
+    mut player_q: SkierScaleQuery,
+    mut shadows_q: Query<&mut Transform, With<JumpShadow>>,
+) {
+    let Ok((mut transform, airborne, children)) = player_q.get_single_mut() else {
+        return;
+    };
+    let Some(airborne) = airborne else {
+        if transform.scale != Vec3::ONE {
+            transform.scale = Vec3::ONE;
+        }
+        return;
+    };
+    let height = airborne.height();
+    let scale = 1.0 + height * SCALE_PER_HEIGHT;
+    transform.scale = Vec3::splat(scale);
+    for &child in children {
+        if let Ok(mut shadow_transform) = shadows_q.get_mut(child) {
+            shadow_transform.translation.y = -(height + 3.0 * SCALE_FACTOR) / scale;
+        }
+    }
+}
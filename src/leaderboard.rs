@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    GameResources,
+    despawn,
+    gamepad::ButtonPresses,
+    NORMAL_BUTTON,
+    player::{RaceClock, FinishedPlayer},
+    course::{CourseId, Course},
+    replay::ReplayPlayback,
+    storage::{GameStorage, Storage, load_ron, save_ron},
+};
+
+pub const LEADERBOARD_SIZE: usize = 10;
+const LEADERBOARD_KEY: &str = "leaderboard";
+const GAMEOVER_VISIBLE_ENTRIES: usize = 5;
+const MAX_NAME_LENGTH: usize = 3;
+const HIGHLIGHT_COLOR: Color = Color::rgb(0.35, 0.75, 0.35);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub time: f32,
+}
+
+/// Best times for every course or seed, sorted from the fastest.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    tables: HashMap<CourseId, Vec<LeaderboardEntry>>,
+}
+
+impl Leaderboard {
+    pub fn entries(&self, course_id: &CourseId) -> &[LeaderboardEntry] {
+        self.tables.get(course_id).map_or(&[], |entries| entries.as_slice())
+    }
+
+    pub fn qualifies(&self, course_id: &CourseId, time: f32) -> bool {
+        // a full table has a last place to beat
+        match self.entries(course_id).get(LEADERBOARD_SIZE - 1) {
+            Some(last) => time < last.time,
+            None => true,
+        }
+    }
+
+    /// Returns the rank of the inserted entry, `None` when it did not make it into the table.
+    pub fn insert(&mut self, course_id: &CourseId, entry: LeaderboardEntry) -> Option<usize> {
+        let entries = self.tables.entry(course_id.clone()).or_default();
+        let rank = entries.iter().position(|other| entry.time < other.time).unwrap_or(entries.len());
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+        entries.insert(rank, entry);
+        entries.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+
+    pub fn load(storage: &dyn Storage) -> Self {
+        load_ron(storage, LEADERBOARD_KEY)
+    }
+
+    pub fn save(&self, storage: &dyn Storage) {
+        save_ron(storage, LEADERBOARD_KEY, self);
+    }
+}
+
+/// Finished run waiting for the player to type in initials.
+#[derive(Resource)]
+pub struct NameEntry {
+    course_id: CourseId,
+    time: f32,
+    name: String,
+}
+
+impl NameEntry {
+    fn push(&mut self, c: char) {
+        if c.is_ascii_alphanumeric() && self.name.len() < MAX_NAME_LENGTH {
+            self.name.push(c.to_ascii_uppercase());
+        }
+    }
+}
+
+#[derive(Component)]
+struct LeaderboardElement;
+
+#[derive(Component)]
+struct NameEntryText;
+
+#[derive(Component)]
+struct LeaderboardBack;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameStorage>()
+            .add_startup_system(load_leaderboard)
+            .add_system(start_name_entry.in_schedule(OnEnter(GameState::GameOver)))
+            .add_systems(
+                (
+                    name_input,
+                    confirm_name.after(name_input),
+                    update_name_text.after(name_input),
+                ).in_set(OnUpdate(GameState::GameOver))
+            )
+            .add_systems(
+                (
+                    submit_name_entry,
+                    despawn::<LeaderboardElement>,
+                ).in_schedule(OnExit(GameState::GameOver))
+            )
+            .add_system(setup_leaderboard.in_schedule(OnEnter(GameState::Leaderboard)))
+            .add_system(back_interaction.in_set(OnUpdate(GameState::Leaderboard)))
+            .add_system(despawn::<LeaderboardElement>.in_schedule(OnExit(GameState::Leaderboard)));
+    }
+}
+
+fn load_leaderboard(
+    mut commands: Commands,
+    storage: Res<GameStorage>,
+) {
+    commands.insert_resource(Leaderboard::load(storage.0.as_ref()));
+}
+
+fn start_name_entry(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    leaderboard: Res<Leaderboard>,
+    course_id: Option<Res<CourseId>>,
+    playback: Option<Res<ReplayPlayback>>,
+    player_q: Query<&RaceClock, FinishedPlayer>,
+) {
+    let Some(course_id) = course_id else {
+        return;
+    };
+    let text_style = TextStyle {
+        font: game_resources.font_handle.clone(),
+        font_size: 16.0,
+        color: Color::BLACK,
+    };
+    let time = player_q.get_single().ok().map(|clock| clock.time());
+    match time {
+        Some(time) if playback.is_none() && leaderboard.qualifies(&course_id, time) => {
+            commands.insert_resource(NameEntry {
+                course_id: course_id.clone(),
+                time,
+                name: String::new(),
+            });
+            commands.spawn((
+                TextBundle::from_section(
+                    get_name_entry_text(""),
+                    text_style,
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(250.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    ..default()
+                }),
+                NameEntryText,
+                LeaderboardElement,
+            ));
+        },
+        _ => {
+            spawn_table(&mut commands, text_style, leaderboard.entries(&course_id), GAMEOVER_VISIBLE_ENTRIES, None);
+        }
+    }
+}
+
+fn name_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ev_character: EventReader<ReceivedCharacter>,
+    name_entry: Option<ResMut<NameEntry>>,
+) {
+    let Some(mut name_entry) = name_entry else {
+        ev_character.clear();
+        return;
+    };
+    for ev in ev_character.iter() {
+        name_entry.push(ev.char);
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        name_entry.name.pop();
+    }
+}
+
+/// Enter submits the initials and shows where they ended up in the table.
+fn confirm_name(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    storage: Res<GameStorage>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut leaderboard: ResMut<Leaderboard>,
+    name_entry: Option<Res<NameEntry>>,
+    text_q: Query<Entity, With<NameEntryText>>,
+) {
+    let Some(name_entry) = name_entry else {
+        return;
+    };
+    if !keyboard_input.just_pressed(KeyCode::Return) || name_entry.name.is_empty() {
+        return;
+    }
+
+    let rank = submit(&mut leaderboard, storage.0.as_ref(), &name_entry);
+    commands.remove_resource::<NameEntry>();
+    for entity in text_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let text_style = TextStyle {
+        font: game_resources.font_handle.clone(),
+        font_size: 16.0,
+        color: Color::BLACK,
+    };
+    spawn_table(&mut commands, text_style, leaderboard.entries(&name_entry.course_id), GAMEOVER_VISIBLE_ENTRIES, rank);
+}
+
+fn update_name_text(
+    name_entry: Option<Res<NameEntry>>,
+    mut text_q: Query<&mut Text, With<NameEntryText>>,
+) {
+    let Some(name_entry) = name_entry else {
+        return;
+    };
+    if !name_entry.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text_q.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = get_name_entry_text(&name_entry.name);
+}
+
+/// Leaving the game over screen keeps the time even when no initials were typed in.
+fn submit_name_entry(
+    mut commands: Commands,
+    storage: Res<GameStorage>,
+    mut leaderboard: ResMut<Leaderboard>,
+    name_entry: Option<ResMut<NameEntry>>,
+) {
+    let Some(mut name_entry) = name_entry else {
+        return;
+    };
+    if name_entry.name.is_empty() {
+        name_entry.name = "???".to_string();
+    }
+    submit(&mut leaderboard, storage.0.as_ref(), &name_entry);
+    commands.remove_resource::<NameEntry>();
+}
+
+fn submit(
+    leaderboard: &mut Leaderboard,
+    storage: &dyn Storage,
+    name_entry: &NameEntry,
+) -> Option<usize> {
+    let rank = leaderboard.insert(&name_entry.course_id, LeaderboardEntry {
+        name: name_entry.name.clone(),
+        time: name_entry.time,
+    });
+    leaderboard.save(storage);
+    rank
+}
+
+fn get_name_entry_text(name: &str) -> String {
+    format!("New best time! Name: {:_<width$}  [Enter]", name, width = MAX_NAME_LENGTH)
+}
+
+fn format_entry(rank: usize, entry: &LeaderboardEntry) -> String {
+    format!("{:>2}. {:<3} {:>8.3}", rank + 1, entry.name, entry.time)
+}
+
+fn get_course_title(
+    course_id: &CourseId,
+    asset_server: &AssetServer,
+    courses: &Assets<Course>,
+) -> String {
+    match course_id {
+        CourseId::Seed(seed) => format!("Seed {}", seed),
+        CourseId::Course(path) => courses.get(&asset_server.get_handle(path.as_str()))
+            .map(|course| course.name.clone())
+            .unwrap_or(path.clone()),
+    }
+}
+
+fn spawn_table(
+    commands: &mut Commands,
+    text_style: TextStyle,
+    entries: &[LeaderboardEntry],
+    visible: usize,
+    highlight: Option<usize>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexStart,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(20.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ..default()
+            },
+            LeaderboardElement,
+        ))
+        .with_children(|builder| {
+            if entries.is_empty() {
+                builder.spawn(TextBundle::from_section("No times yet", text_style.clone()));
+            }
+            for (rank, entry) in entries.iter().enumerate().take(visible) {
+                let mut style = text_style.clone();
+                if highlight == Some(rank) {
+                    style.color = HIGHLIGHT_COLOR;
+                }
+                builder.spawn(TextBundle::from_section(format_entry(rank, entry), style));
+            }
+            if let Some(rank) = highlight.filter(|rank| *rank >= visible) {
+                let mut style = text_style.clone();
+                style.color = HIGHLIGHT_COLOR;
+                builder.spawn(TextBundle::from_section(format_entry(rank, &entries[rank]), style));
+            }
+        });
+}
+
+fn setup_leaderboard(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    asset_server: Res<AssetServer>,
+    courses: Res<Assets<Course>>,
+    leaderboard: Res<Leaderboard>,
+    course_id: Option<Res<CourseId>>,
+) {
+    let text_style = TextStyle {
+        font: game_resources.font_handle.clone(),
+        font_size: 16.0,
+        color: Color::BLACK,
+    };
+    let Some(course_id) = course_id else {
+        return;
+    };
+
+    commands.spawn((
+        TextBundle::from_section(
+            get_course_title(&course_id, &asset_server, &courses),
+            TextStyle {
+                font_size: 24.0,
+                ..text_style.clone()
+            },
+        )
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(250.0),
+                ..default()
+            },
+            margin: UiRect {
+                left: Val::Auto,
+                right: Val::Auto,
+                ..default()
+            },
+            ..default()
+        }),
+        LeaderboardElement,
+    ));
+
+    spawn_table(&mut commands, text_style.clone(), leaderboard.entries(&course_id), LEADERBOARD_SIZE, None);
+
+    commands.spawn((
+        ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(350.0),
+                    ..default()
+                },
+                margin: UiRect {
+                    left: Val::Auto,
+                    right: Val::Auto,
+                    ..default()
+                },
+                padding: UiRect {
+                    left: Val::Px(12.0),
+                    right: Val::Px(12.0),
+                    top: Val::Px(8.0),
+                    bottom: Val::Px(8.0)
+                },
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        },
+        LeaderboardElement,
+        LeaderboardBack,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "Back",
+            TextStyle {
+                font_size: 24.0,
+                ..text_style
+            },
+        ));
+    });
+}
+
+fn back_interaction(
+    mut presses: ButtonPresses<LeaderboardBack>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut app_state: ResMut<NextState<GameState>>,
+) {
+    let clicked = presses.iter().next().is_some();
+    if clicked || keyboard_input.just_pressed(KeyCode::Escape) {
+        app_state.set(GameState::MainMenu);
+    }
+}
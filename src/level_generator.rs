@@ -1,7 +1,7 @@
-use bevy::{prelude::*, math::vec2};
-use rand::{Rng, seq::SliceRandom};
+use bevy::{prelude::*};
+use rand::{Rng, SeedableRng, seq::SliceRandom, rngs::StdRng};
 
-use crate::{GameState, GameResources, obstacle::{ObstacleType, TREE_COLLIDABLE_DIMENSIONS, TREE_COLLIDABLE_OFFSETS, STONE_COLLIDABLE_DIMENSIONS, STONE_COLLIDABLE_OFFSETS, Obstacle}, player::{self, PLAYER_Z_INDEX}, collidable::Collidable, Alive, debug::DebugMarker, finish::Finish, posts::{PostsSpawner, PostColor, POST_DISTANCE, HIT_DETECTION_OFFSET, Posts}};
+use crate::{RaceSchedule, GameResources, obstacle::{ObstacleType, spawn_obstacle}, player, finish::spawn_finish, posts::{PostsSpawner, spawn_gate}, surface::{SurfaceType, spawn_surface}};
 
 #[derive(Resource)]
 pub struct LevelGeneratorSettings {
@@ -11,6 +11,27 @@ pub struct LevelGeneratorSettings {
     pub width: usize,
     pub height: usize,
     pub starting_difficulty: f32,
+    /// Rows between two ramps.
+    pub ramp_interval: usize,
+    /// Chance of a surface patch starting on a row.
+    pub surface_chance: f32,
+    pub seed: u64,
+}
+
+impl LevelGeneratorSettings {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            tile_size: 120.0,
+            displacement: 30.0,
+            start_offset_y: -350.0,
+            width: 50,
+            height: 400,
+            starting_difficulty: 0.3,
+            ramp_interval: 12,
+            surface_chance: 0.08,
+            seed,
+        }
+    }
 }
 
 pub struct LevelGeneratorPlugin;
@@ -19,7 +40,7 @@ pub struct LevelGeneratorPlugin;
 impl Plugin for LevelGeneratorPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_system(generate.after(player::setup).in_schedule(OnEnter(GameState::Playing)));
+            .add_system(generate.after(player::setup).in_schedule(RaceSchedule::Setup));
     }
 }
 
@@ -31,7 +52,7 @@ fn generate(
     let Some(levelgenerator) = levelgenerator else {
         return;
     };
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(levelgenerator.seed);
     let width_halved = (levelgenerator.width / 2) as isize;
 
     for ty in 0..levelgenerator.height as isize {
@@ -49,107 +70,48 @@ fn generate(
                 continue;
             };
 
-            let (sprite_rect, collidable_dimension, offsets, offset_z) = match obstacle_type {
-                ObstacleType::Tree => (game_resources.tree, TREE_COLLIDABLE_DIMENSIONS, TREE_COLLIDABLE_OFFSETS, 0.2),
-                ObstacleType::Stone => (game_resources.stone, STONE_COLLIDABLE_DIMENSIONS, STONE_COLLIDABLE_OFFSETS, 0.1)
-            };
+            spawn_obstacle(&mut commands, &game_resources, obstacle_type, x, y);
+        }
+    }
+    // ramps come from their own stream so they don't reshuffle the obstacles of existing seeds
+    let mut ramp_rng = StdRng::seed_from_u64(levelgenerator.seed.wrapping_add(1));
+    for ty in (levelgenerator.ramp_interval..levelgenerator.height).step_by(levelgenerator.ramp_interval.max(1)) {
+        let tx = ramp_rng.gen_range(-width_halved / 4..=width_halved / 4);
+        let x = tx as f32 * levelgenerator.tile_size;
+        let y = -(ty as f32) * levelgenerator.tile_size + levelgenerator.start_offset_y;
+        spawn_obstacle(&mut commands, &game_resources, &ObstacleType::Ramp, x, y);
+    }
 
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
-                        rect: Some(sprite_rect.clone()),
-                        ..default()
-                    },
-                    texture: game_resources.image_handle.clone(),
-                    transform: Transform::from_xyz(x, y, PLAYER_Z_INDEX + 1.0 + offset_z),
-                    ..default()
-                },
-                Collidable::new(x, y, collidable_dimension.0, collidable_dimension.1, offsets.0, offsets.1),
-                Alive,
-                Obstacle,
-            ))
-            .with_children(|parent| {
-                parent.spawn((
-                    SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::rgb(0.75, 0.25, 0.25),
-                            custom_size: Some(Vec2::new(collidable_dimension.0 * 2.0, collidable_dimension.1 * 2.0)),
-                            ..default()
-                        },
-                        transform: Transform::from_xyz(offsets.0, offsets.1, 2.),
-                        visibility: Visibility::Hidden,
-                        ..default()
-                    },
-                    DebugMarker
-                ));
-            })
-            ;
+    let mut surface_rng = StdRng::seed_from_u64(levelgenerator.seed.wrapping_add(2));
+    let mut ty = 0;
+    while ty < levelgenerator.height {
+        if surface_rng.gen_range(0.0..1.0) > levelgenerator.surface_chance {
+            ty += 1;
+            continue;
         }
+        let kinds = [SurfaceType::Ice, SurfaceType::Powder, SurfaceType::Moguls];
+        let Some(&kind) = kinds.choose(&mut surface_rng) else {
+            break;
+        };
+        let width = surface_rng.gen_range(3..=8) as f32 * levelgenerator.tile_size;
+        let height = surface_rng.gen_range(2..=5);
+        let x = surface_rng.gen_range(-width_halved / 4..=width_halved / 4) as f32 * levelgenerator.tile_size;
+        let top = -(ty as f32) * levelgenerator.tile_size + levelgenerator.start_offset_y;
+        let bottom = top - height as f32 * levelgenerator.tile_size;
+        spawn_surface(&mut commands, kind, Rect::new(x - width / 2.0, bottom, x + width / 2.0, top));
+        ty += height;
     }
+
     let finish_line_y = -(levelgenerator.height as f32) * levelgenerator.tile_size + levelgenerator.start_offset_y + 100.0;
-    let post_spawner =  PostsSpawner::new();
+    let post_spawner =  PostsSpawner::new(&mut rng);
     let max_post_y = finish_line_y + 300.0;
     for (color, x, y) in post_spawner {
-        commands.spawn((
-            SpatialBundle {
-                transform: Transform::from_xyz(x, y, PLAYER_Z_INDEX + 1.1),
-                ..default()
-            },
-            Alive,
-            Collidable::new(x, y, (POST_DISTANCE - 1.0) / 2.0, HIT_DETECTION_OFFSET / 2.0, 0.0, HIT_DETECTION_OFFSET / 2.0),
-            Posts
-        ))
-        .with_children(|parent| {
-            let sprite_rect = match color {
-                PostColor::Blue => game_resources.blue_post,
-                PostColor::Red => game_resources.red_post,
-            };
-
-            parent.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
-                        rect: Some(sprite_rect.clone()),
-                        ..default()
-                    },
-                    texture: game_resources.image_handle.clone(),
-                    transform: Transform::from_xyz(-POST_DISTANCE / 2.0, 0.0, 0.),
-                    ..default()
-                },
-            ));
-            parent.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
-                        rect: Some(sprite_rect.clone()),
-                        ..default()
-                    },
-                    texture: game_resources.image_handle.clone(),
-                    transform: Transform::from_xyz(POST_DISTANCE / 2.0, 0.0, 0.),
-                    ..default()
-                },
-            ));
-        });
+        spawn_gate(&mut commands, &game_resources, &color, x, y);
 
         if max_post_y > y {
             break;
         }
     }
 
-    commands.spawn((
-        SpriteBundle {
-            sprite: Sprite {
-                color: Color::BLACK,
-                custom_size: Some(Vec2::new(
-                    levelgenerator.width as f32 * levelgenerator.tile_size,
-                    8.0
-                )),
-                ..default()
-            },
-            transform: Transform::from_xyz(0.0, finish_line_y, 0.0),
-            ..default()
-        },
-        Finish(finish_line_y),
-    ));
-}
\ No newline at end of file
+    spawn_finish(&mut commands, 0.0, finish_line_y, levelgenerator.width as f32 * levelgenerator.tile_size);
+}
@@ -0,0 +1,255 @@
+use animation::AnimationPlugin;
+use bevy::{prelude::*, ecs::schedule::ScheduleLabel};
+
+use camera::CameraPlugin;
+use finish::FinishPlugin;
+use level_generator::LevelGeneratorPlugin;
+use obstacle::ObstaclePlugin;
+use player::{PlayerPlugin};
+use posts::PostsPlugin;
+use simulation::SimulationPlugin;
+use sounds::PostHitEvent;
+use spatialtree::SpatialTree;
+use stuneffect::StunPlugin;
+use trail::TrailPlugin;
+use uicontrols::UiControlsPlugin;
+use yeti::YetiPlugin;
+use jump::JumpPlugin;
+use surface::SurfacePlugin;
+use pause::PauseTransition;
+use settings::Settings;
+
+pub mod player;
+pub mod obstacle;
+pub mod collidable;
+pub mod menu;
+pub mod debug;
+pub mod tutorial;
+pub mod gameover;
+pub mod trail;
+pub mod posts;
+pub mod uicontrols;
+pub mod yeti;
+pub mod animation;
+pub mod stuneffect;
+pub mod music;
+pub mod sounds;
+pub mod spatialtree;
+pub mod camera;
+pub mod level_generator;
+pub mod finish;
+pub mod course;
+pub mod editor;
+pub mod replay;
+pub mod ghost;
+pub mod simulation;
+#[cfg(feature = "headless")]
+pub mod headless;
+pub mod storage;
+pub mod leaderboard;
+pub mod splits;
+pub mod jump;
+pub mod surface;
+pub mod navigation;
+pub mod gamepad;
+pub mod controls;
+pub mod pause;
+pub mod settings;
+pub mod mixer;
+/*
+TODO
+- sound
+    - pridat zvuky pre yetiho a lyze
+- yeti
+    1. pridat animaciu ako zozerie hraca
+    2. pridat walking animaciu na strany
+ */
+
+pub const SPATIAL_TILE_SIZE: f32 = 240.0;
+pub const SCREEN_WIDTH: f32 = 640.0;
+pub const SCREEN_HEIGHT: f32 = 480.0;
+pub const SPRITE_SIZE: f32 = 12.0;
+pub const SCALE_FACTOR: f32 = 4.0;
+const SELECTED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+const NORMAL_BUTTON: Color = Color::rgb(1.0, 1.0, 1.0);
+const FOCUSED_TEXT: Color = Color::rgb(0.2, 0.4, 0.9);
+
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+pub enum GameState {
+    Playing,
+    #[default]
+    MainMenu,
+    GameOver,
+    Editor,
+    Leaderboard,
+    Controls,
+    Paused,
+    Settings,
+}
+
+/// Schedules that start and end a race, pausing and resuming skip both.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RaceSchedule {
+    /// Runs on `OnEnter(GameState::Playing)` unless the race is only resumed.
+    Setup,
+    /// Runs when leaving `GameState::GameOver`, and when leaving `GameState::Paused` for anything but resuming.
+    Teardown,
+}
+
+/// Plugins that simulate the race, they don't need a window, renderer or audio.
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<PostHitEvent>()
+            .insert_resource(SpatialTree::new(SPATIAL_TILE_SIZE))
+            .add_state::<GameState>()
+            .init_resource::<Settings>()
+            .init_schedule(RaceSchedule::Setup)
+            .init_schedule(RaceSchedule::Teardown)
+            .add_system(start_race.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(end_race.in_schedule(OnExit(GameState::GameOver)))
+            .add_system(end_race.in_schedule(OnExit(GameState::Paused)))
+            .add_system(finish_pause.in_schedule(OnEnter(GameState::Paused)))
+            .add_plugin(SimulationPlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(ObstaclePlugin)
+            .add_plugin(JumpPlugin)
+            .add_plugin(SurfacePlugin)
+            .add_plugin(PostsPlugin)
+            .add_plugin(TrailPlugin)
+            .add_plugin(YetiPlugin)
+            .add_plugin(AnimationPlugin)
+            .add_plugin(StunPlugin)
+            .add_plugin(CameraPlugin)
+            .add_plugin(LevelGeneratorPlugin)
+            .add_plugin(FinishPlugin)
+            .add_plugin(UiControlsPlugin);
+    }
+}
+
+fn start_race(world: &mut World) {
+    if world.remove_resource::<PauseTransition>().is_none() {
+        world.run_schedule(RaceSchedule::Setup);
+    }
+}
+
+fn end_race(world: &mut World) {
+    if !world.contains_resource::<PauseTransition>() {
+        world.run_schedule(RaceSchedule::Teardown);
+    }
+}
+
+fn finish_pause(world: &mut World) {
+    world.remove_resource::<PauseTransition>();
+}
+
+/// Run condition for `OnExit(GameState::Playing)` systems that should not run when the race is only paused.
+pub fn not_pausing(pause_transition: Option<Res<PauseTransition>>) -> bool {
+    pause_transition.is_none()
+}
+
+#[derive(Component)]
+pub struct Alive;
+
+#[derive(Resource)]
+pub struct GameResources {
+    image_handle: Handle<Image>,
+    font_handle: Handle<Font>,
+    sprite_size: f32,
+    down: Rect,
+    sides: Vec<Rect>,
+    fall_down: Rect,
+    tree: Rect,
+    stone: Rect,
+    red_post: Rect,
+    blue_post: Rect,
+    yeti_run: Vec<Rect>,
+    yeti_fallen: Rect,
+    yeti_step: Rect,
+    yeti_side: Vec<Rect>,
+    yeti_taunt: Vec<Rect>,
+    yeti_eat: Vec<Rect>,
+    stun: Rect,
+}
+
+impl GameResources {
+    pub fn new(image_handle: Handle<Image>, font_handle: Handle<Font>) -> Self {
+        let yeti_run = vec![
+            Rect::new(10. * SPRITE_SIZE, 0., 11. * SPRITE_SIZE, SPRITE_SIZE),
+            Rect::new(11. * SPRITE_SIZE, 0., 12. * SPRITE_SIZE, SPRITE_SIZE),
+            Rect::new(12. * SPRITE_SIZE, 0., 13. * SPRITE_SIZE, SPRITE_SIZE),
+        ];
+        Self {
+            image_handle,
+            font_handle,
+            sprite_size: SPRITE_SIZE * SCALE_FACTOR,
+            down: Rect::new(0. * SPRITE_SIZE, 0., 1. * SPRITE_SIZE, SPRITE_SIZE),
+            sides: vec![
+                Rect::new(1. * SPRITE_SIZE, 0., 2. * SPRITE_SIZE, SPRITE_SIZE),
+                Rect::new(2. * SPRITE_SIZE, 0., 3. * SPRITE_SIZE, SPRITE_SIZE),
+                Rect::new(3. * SPRITE_SIZE, 0., 4. * SPRITE_SIZE, SPRITE_SIZE),
+                Rect::new(4. * SPRITE_SIZE, 0., 5. * SPRITE_SIZE, SPRITE_SIZE),
+            ],
+            fall_down: Rect::new(5. * SPRITE_SIZE, 0., 6. * SPRITE_SIZE, SPRITE_SIZE),
+            tree: Rect::new(6. * SPRITE_SIZE, 0., 7. * SPRITE_SIZE, SPRITE_SIZE),
+            stone: Rect::new(7. * SPRITE_SIZE, 0., 8. * SPRITE_SIZE, SPRITE_SIZE),
+            red_post: Rect::new(8. * SPRITE_SIZE, 0., 9. * SPRITE_SIZE, SPRITE_SIZE),
+            blue_post: Rect::new(9. * SPRITE_SIZE, 0., 10. * SPRITE_SIZE, SPRITE_SIZE),
+            yeti_run: yeti_run.clone(),
+            yeti_fallen: Rect::new(13. * SPRITE_SIZE, 0., 14. * SPRITE_SIZE, SPRITE_SIZE),
+            yeti_step: Rect::new(14. * SPRITE_SIZE, 0., 15. * SPRITE_SIZE, SPRITE_SIZE),
+            // sprites.aseprite has no side, taunt or eat frames yet (see the yeti TODO above), the run cycle stands in for them
+            yeti_side: yeti_run.clone(),
+            yeti_taunt: vec![yeti_run[0], yeti_run[2]],
+            yeti_eat: vec![yeti_run[0], yeti_run[1], yeti_run[2], yeti_run[1], yeti_run[0]],
+            stun: Rect::new(15. * SPRITE_SIZE, 0., 16. * SPRITE_SIZE, SPRITE_SIZE),
+        }
+    }
+}
+
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let image_handle = asset_server.load("spritesheet.png");
+    let font_handle = asset_server.load("QuinqueFive.ttf");
+
+    commands.insert_resource(GameResources::new(image_handle, font_handle));
+    commands.spawn(Camera2dBundle::default());
+}
+
+fn cleanup<T: Component>(
+    mut commands: Commands,
+    mut spatial_tree: ResMut<SpatialTree>,
+    window: Query<&Window>,
+    camera_q: Query<&Transform, With<Camera>>,
+    t_q: Query<(Entity, &Transform), With<T>>
+) {
+    let Ok(transform_camera) = camera_q.get_single() else {
+        return;
+    };
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let offset = window.height() * 0.6;
+
+    for (entity, transform_t) in t_q.iter() {
+        if transform_camera.translation.y + offset < transform_t.translation.y {
+            spatial_tree.remove(entity);
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn despawn<T: Component>(
+    mut commands: Commands,
+    mut spatial_tree: ResMut<SpatialTree>,
+    components_q: Query<Entity, With<T>>
+) {
+    for entity in components_q.iter() {
+        spatial_tree.remove(entity);
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,6 +1,6 @@
-use bevy::{prelude::*};
+use bevy::{prelude::*, ecs::system::SystemParam};
 
-use crate::{GameState, GameResources, despawn, SELECTED_BUTTON, music::MusicResource, uicontrols::ControlScheme, NORMAL_BUTTON, level_generator::LevelGeneratorSettings};
+use crate::{GameState, GameResources, despawn, SELECTED_BUTTON, mixer::AudioMixer, NORMAL_BUTTON, course::{CourseLibrary, SelectedCourse, Course, CourseId, get_course_path, select_course}, replay::{LastReplay, start_replay}, posts::GateRules, player::SkiProfile, yeti::YetiSettings, controls::{Action, KeyBindings, key_name}, gamepad::ButtonPresses};
 
 
 pub struct MenuPlugin;
@@ -10,10 +10,85 @@ enum MainMenuItem {
     Play,
     MusicOn,
     MusicOff,
-    ControlMobile,
-    ControlDesktop,
+    NewSeed,
+    Course,
+    Editor,
+    Replay,
+    Leaderboard,
+    GatesPenalty,
+    GatesStrict,
+    SkiPhysics,
+    SkiArcade,
+    YetiDifficulty,
+    RubberBanding,
+    Controls,
+    Settings,
 }
 
+const MAX_SEED_LENGTH: usize = 20;
+
+#[derive(Resource)]
+struct SeedInput(String);
+
+impl SeedInput {
+    fn random() -> Self {
+        Self(rand::random::<u64>().to_string())
+    }
+
+    fn push(&mut self, c: char) {
+        if !c.is_ascii_digit() || self.0.len() >= MAX_SEED_LENGTH {
+            return;
+        }
+        let mut value = self.0.clone();
+        value.push(c);
+        if value.parse::<u64>().is_ok() {
+            self.0 = value;
+        }
+    }
+}
+
+#[derive(Component)]
+struct SeedText;
+
+/// Index into `CourseLibrary`, `None` means a generated course.
+#[derive(Resource, Default)]
+struct CourseChoice(Option<usize>);
+
+#[derive(Component)]
+struct CourseText;
+
+/// Race and audio options toggled from the menu.
+#[derive(SystemParam)]
+struct MenuOptions<'w> {
+    mixer: ResMut<'w, AudioMixer>,
+    gate_rules: ResMut<'w, GateRules>,
+    ski_profile: ResMut<'w, SkiProfile>,
+    yeti_settings: ResMut<'w, YetiSettings>,
+}
+
+/// Seed or course the next race is played on.
+#[derive(SystemParam)]
+struct CourseSelection<'w> {
+    seed_input: ResMut<'w, SeedInput>,
+    course_choice: ResMut<'w, CourseChoice>,
+    course_library: Res<'w, CourseLibrary>,
+    courses: Res<'w, Assets<Course>>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+impl CourseSelection<'_> {
+    fn course_id(&mut self) -> CourseId {
+        get_course_id(&mut self.seed_input, &self.course_choice, &self.course_library, &self.asset_server)
+    }
+
+    fn course_name(&self) -> String {
+        get_course_name(&self.course_choice, &self.course_library, &self.courses)
+    }
+}
+
+#[derive(Component)]
+struct DifficultyText;
+
 
 struct MenuItemSelected(MainMenuItem);
 
@@ -24,11 +99,18 @@ impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_event::<MenuItemSelected>()
+            .insert_resource(SeedInput::random())
+            .init_resource::<CourseChoice>()
             .add_system(setup.in_schedule(OnEnter(GameState::MainMenu)))
             .add_systems(
                 (
                     controls_interaction,
                     reset_buttons.after(controls_interaction),
+                    seed_input,
+                    update_seed_text.after(seed_input).after(controls_interaction),
+                    update_course_text.after(controls_interaction),
+                    update_yeti_buttons.after(controls_interaction),
+                    update_music_buttons.after(controls_interaction),
                 ).in_set(OnUpdate(GameState::MainMenu))
             )
             .add_system(despawn::<Menu>.in_schedule(OnExit(GameState::MainMenu)));
@@ -42,16 +124,16 @@ fn reset_buttons(
     for item in ev_menuitemselected.iter() {
         for (mut color, menu_item) in button_q.iter_mut() {
             match (&item.0, menu_item) {
-                (MainMenuItem::ControlDesktop, MainMenuItem::ControlMobile) |
-                (MainMenuItem::ControlMobile, MainMenuItem::ControlDesktop) |
-                (MainMenuItem::MusicOff, MainMenuItem::MusicOn) |
-                (MainMenuItem::MusicOn, MainMenuItem::MusicOff) => {
+                (MainMenuItem::GatesPenalty, MainMenuItem::GatesStrict) |
+                (MainMenuItem::GatesStrict, MainMenuItem::GatesPenalty) |
+                (MainMenuItem::SkiPhysics, MainMenuItem::SkiArcade) |
+                (MainMenuItem::SkiArcade, MainMenuItem::SkiPhysics) => {
                     *color = get_button_color(false);
                 },
-                (MainMenuItem::ControlDesktop, MainMenuItem::ControlDesktop) |
-                (MainMenuItem::ControlMobile, MainMenuItem::ControlMobile) |
-                (MainMenuItem::MusicOff, MainMenuItem::MusicOff) |
-                (MainMenuItem::MusicOn, MainMenuItem::MusicOn) => {
+                (MainMenuItem::GatesPenalty, MainMenuItem::GatesPenalty) |
+                (MainMenuItem::GatesStrict, MainMenuItem::GatesStrict) |
+                (MainMenuItem::SkiPhysics, MainMenuItem::SkiPhysics) |
+                (MainMenuItem::SkiArcade, MainMenuItem::SkiArcade) => {
                     *color = get_button_color(true);
                 },
                 _ => {}
@@ -61,53 +143,189 @@ fn reset_buttons(
 }
 
 fn controls_interaction(
-    interaction_query: Query<
-        (&Interaction, &MainMenuItem),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut presses: ButtonPresses<MainMenuItem>,
     mut commands: Commands,
     mut app_state: ResMut<NextState<GameState>>,
-    mut music_resource: ResMut<MusicResource>,
-    mut control_scheme: ResMut<ControlScheme>,
+    mut menu_options: MenuOptions,
     mut ev_menuitemselected: EventWriter<MenuItemSelected>,
-    audio_sinks: Res<Assets<AudioSink>>,
+    mut course_selection: CourseSelection,
+    last_replay: Option<Res<LastReplay>>,
 ) {
-    for (interaction, menu_item) in &interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                match menu_item {
-                    MainMenuItem::Play => {
-                        commands.insert_resource(LevelGeneratorSettings {
-                            tile_size: 120.0,
-                            displacement: 30.0,
-                            start_offset_y: -350.0,
-                            width: 50,
-                            height: 400,
-                            starting_difficulty: 0.3
-                        });
-                        app_state.set(GameState::Playing);
-                    },
-                    MainMenuItem::MusicOn => {
-                        if let Some(sink) = audio_sinks.get(&music_resource.controller) {
-                            sink.play();
-                            music_resource.playing = true;
-                        }
-                    },
-                    MainMenuItem::MusicOff => {
-                        if let Some(sink) = audio_sinks.get(&music_resource.controller) {
-                            sink.pause();
-                            music_resource.playing = false;
-                        }
-                    },
-                    MainMenuItem::ControlDesktop => {
-                        control_scheme.set_desktop();
-                    },
-                    MainMenuItem::ControlMobile => {
-                        control_scheme.set_mobile();
-                    }
+    for menu_item in presses.iter() {
+        match menu_item {
+            MainMenuItem::Play => {
+                let course_id = course_selection.course_id();
+                select_course(&mut commands, &course_selection.asset_server, &course_id);
+                app_state.set(GameState::Playing);
+            },
+            MainMenuItem::Controls => {
+                app_state.set(GameState::Controls);
+            },
+            MainMenuItem::Settings => {
+                app_state.set(GameState::Settings);
+            },
+            MainMenuItem::Leaderboard => {
+                let course_id = course_selection.course_id();
+                commands.insert_resource(course_id);
+                app_state.set(GameState::Leaderboard);
+            },
+            MainMenuItem::MusicOn => {
+                menu_options.mixer.music.muted = false;
+            },
+            MainMenuItem::MusicOff => {
+                menu_options.mixer.music.muted = true;
+            },
+            MainMenuItem::GatesPenalty => {
+                *menu_options.gate_rules = GateRules::Penalty;
+            },
+            MainMenuItem::GatesStrict => {
+                *menu_options.gate_rules = GateRules::Strict;
+            },
+            MainMenuItem::SkiPhysics => {
+                *menu_options.ski_profile = SkiProfile::Physics;
+            },
+            MainMenuItem::SkiArcade => {
+                *menu_options.ski_profile = SkiProfile::Arcade;
+            },
+            MainMenuItem::YetiDifficulty => {
+                menu_options.yeti_settings.difficulty = menu_options.yeti_settings.difficulty.next();
+            },
+            MainMenuItem::RubberBanding => {
+                menu_options.yeti_settings.rubber_banding = !menu_options.yeti_settings.rubber_banding;
+            },
+            MainMenuItem::NewSeed => {
+                *course_selection.seed_input = SeedInput::random();
+            },
+            MainMenuItem::Editor => {
+                match course_selection.course_choice.0.and_then(|index| course_selection.course_library.courses.get(index)) {
+                    Some(handle) => commands.insert_resource(SelectedCourse(handle.clone())),
+                    None => commands.remove_resource::<SelectedCourse>(),
                 };
-                ev_menuitemselected.send(MenuItemSelected(menu_item.clone()));
+                app_state.set(GameState::Editor);
+            },
+            MainMenuItem::Replay => {
+                if let Some(last_replay) = &last_replay {
+                    start_replay(&mut commands, &course_selection.asset_server, &mut app_state, &last_replay.0);
+                }
             },
+            MainMenuItem::Course => {
+                let courses = course_selection.course_library.courses.len();
+                course_selection.course_choice.0 = match course_selection.course_choice.0 {
+                    None if courses > 0 => Some(0),
+                    Some(index) if index + 1 < courses => Some(index + 1),
+                    _ => None,
+                };
+            }
+        };
+        ev_menuitemselected.send(MenuItemSelected(menu_item.clone()));
+    }
+}
+
+fn get_course_id(
+    seed_input: &mut SeedInput,
+    course_choice: &CourseChoice,
+    course_library: &CourseLibrary,
+    asset_server: &AssetServer,
+) -> CourseId {
+    let course_path = course_choice.0
+        .and_then(|index| course_library.courses.get(index))
+        .and_then(|handle| get_course_path(asset_server, handle));
+    match course_path {
+        Some(path) => CourseId::Course(path),
+        None => {
+            if seed_input.0.parse::<u64>().is_err() {
+                *seed_input = SeedInput::random();
+            }
+            CourseId::Seed(seed_input.0.parse().unwrap_or_default())
+        }
+    }
+}
+
+fn seed_input(
+    mut seed_input: ResMut<SeedInput>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ev_character: EventReader<ReceivedCharacter>,
+) {
+    for ev in ev_character.iter() {
+        seed_input.push(ev.char);
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        seed_input.0.pop();
+    }
+}
+
+fn update_seed_text(
+    seed_input: Res<SeedInput>,
+    mut text_q: Query<&mut Text, With<SeedText>>,
+) {
+    if !seed_input.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text_q.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = format!("Seed {}", seed_input.0);
+}
+
+fn get_course_name(
+    course_choice: &CourseChoice,
+    course_library: &CourseLibrary,
+    courses: &Assets<Course>,
+) -> String {
+    let Some(index) = course_choice.0 else {
+        return "Random".to_string();
+    };
+    course_library.courses.get(index)
+        .and_then(|handle| courses.get(handle))
+        .map(|course| course.name.clone())
+        .unwrap_or("Loading".to_string())
+}
+
+fn update_course_text(
+    course_choice: Res<CourseChoice>,
+    course_library: Res<CourseLibrary>,
+    courses: Res<Assets<Course>>,
+    mut text_q: Query<&mut Text, With<CourseText>>,
+) {
+    let Ok(mut text) = text_q.get_single_mut() else {
+        return;
+    };
+    let name = get_course_name(&course_choice, &course_library, &courses);
+    if text.sections[0].value != name {
+        text.sections[0].value = name;
+    }
+}
+
+fn update_yeti_buttons(
+    yeti_settings: Res<YetiSettings>,
+    mut text_q: Query<&mut Text, With<DifficultyText>>,
+    mut button_q: Query<(&mut BackgroundColor, &MainMenuItem), With<Button>>,
+) {
+    if !yeti_settings.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = text_q.get_single_mut() {
+        text.sections[0].value = yeti_settings.difficulty.name().to_string();
+    }
+    for (mut color, menu_item) in button_q.iter_mut() {
+        if let MainMenuItem::RubberBanding = menu_item {
+            *color = get_button_color(yeti_settings.rubber_banding);
+        }
+    }
+}
+
+/// The music key works on the menu too, so the buttons follow the mixer rather than the clicks.
+fn update_music_buttons(
+    mixer: Res<AudioMixer>,
+    mut button_q: Query<(&mut BackgroundColor, &MainMenuItem), With<Button>>,
+) {
+    if !mixer.is_changed() {
+        return;
+    }
+    for (mut color, menu_item) in button_q.iter_mut() {
+        match menu_item {
+            MainMenuItem::MusicOn => *color = get_button_color(!mixer.music.muted),
+            MainMenuItem::MusicOff => *color = get_button_color(mixer.music.muted),
             _ => {}
         }
     }
@@ -124,8 +342,9 @@ fn get_button_color(toggle: bool) -> BackgroundColor {
 fn setup(
     mut commands: Commands,
     game_resources: Res<GameResources>,
-    music_resource: Res<MusicResource>,
-    control_scheme: Res<ControlScheme>,
+    menu_options: MenuOptions,
+    key_bindings: Res<KeyBindings>,
+    course_selection: CourseSelection,
 ) {
     let text_style = TextStyle {
         font: game_resources.font_handle.clone(),
@@ -151,38 +370,105 @@ fn setup(
             Menu
         ))
         .with_children(|builder| {
-            builder.spawn((
-                ButtonBundle {
-                    style: Style {
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        position: UiRect {
-                            top: Val::Px(50.0),
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    position: UiRect {
+                        top: Val::Px(50.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            }).with_children(|builder| {
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            margin: UiRect {
+                                right: Val::Px(24.0),
+                                ..default()
+                            },
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
                             ..default()
                         },
-                        margin: UiRect {
-                            left: Val::Auto,
-                            right: Val::Auto,
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    MainMenuItem::Play,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Play",
+                        text_style.clone(),
+                    ));
+                });
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
                             ..default()
                         },
-                        padding: UiRect {
-                            left: Val::Px(12.0),
-                            right: Val::Px(12.0),
-                            top: Val::Px(8.0),
-                            bottom: Val::Px(8.0)
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    MainMenuItem::Editor,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Editor",
+                        text_style.clone(),
+                    ));
+                });
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            margin: UiRect {
+                                left: Val::Px(24.0),
+                                ..default()
+                            },
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
                         },
+                        background_color: NORMAL_BUTTON.into(),
                         ..default()
                     },
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                },
-                MainMenuItem::Play,
-            ))
-            .with_children(|parent| {
-                parent.spawn(TextBundle::from_section(
-                    "Play",
-                    text_style.clone(),
-                ));
+                    MainMenuItem::Replay,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Replay",
+                        text_style.clone(),
+                    ));
+                });
             });
 
             builder.spawn((
@@ -240,7 +526,7 @@ fn setup(
                             },
                             ..default()
                         },
-                        background_color: get_button_color(music_resource.playing),
+                        background_color: get_button_color(!menu_options.mixer.music.muted),
                         ..default()
                     },
                     MainMenuItem::MusicOn,
@@ -265,7 +551,7 @@ fn setup(
                             },
                             ..default()
                         },
-                        background_color: get_button_color(!music_resource.playing),
+                        background_color: get_button_color(menu_options.mixer.music.muted),
                         ..default()
                     },
                     MainMenuItem::MusicOff,
@@ -280,7 +566,7 @@ fn setup(
 
             builder.spawn((
                 TextBundle::from_section(
-                    format!("Controls "),
+                    format!("Options "),
                     text_style.clone(),
                 )
                 .with_text_alignment(text_alignment)
@@ -333,14 +619,14 @@ fn setup(
                             },
                             ..default()
                         },
-                        background_color: get_button_color(control_scheme.is_mobile()),
+                        background_color: NORMAL_BUTTON.into(),
                         ..default()
                     },
-                    MainMenuItem::ControlMobile,
+                    MainMenuItem::Settings,
                 ))
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
-                        "Mobile",
+                        "Settings",
                         text_style.clone(),
                     ));
                 });
@@ -358,14 +644,422 @@ fn setup(
                             },
                             ..default()
                         },
-                        background_color: get_button_color(control_scheme.is_desktop()),
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    MainMenuItem::Controls,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Keys",
+                        text_style.clone(),
+                    ));
+                });
+            });
+
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    position: UiRect {
+                        top: Val::Px(260.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            }).with_children(|builder| {
+                builder.spawn(
+                    TextBundle::from_section(
+                        "Course ",
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        margin: UiRect {
+                            right: Val::Px(24.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                );
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    MainMenuItem::Course,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            course_selection.course_name(),
+                            text_style.clone(),
+                        ),
+                        CourseText,
+                    ));
+                });
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            margin: UiRect {
+                                left: Val::Px(24.0),
+                                ..default()
+                            },
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    MainMenuItem::Leaderboard,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Scores",
+                        text_style.clone(),
+                    ));
+                });
+            });
+
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    position: UiRect {
+                        top: Val::Px(270.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            }).with_children(|builder| {
+                builder.spawn((
+                    TextBundle::from_section(
+                        format!("Seed {}", course_selection.seed_input.0),
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        margin: UiRect {
+                            right: Val::Px(24.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                    SeedText,
+                ));
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    MainMenuItem::NewSeed,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "New",
+                        text_style.clone(),
+                    ));
+                });
+            });
+
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    position: UiRect {
+                        top: Val::Px(280.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            }).with_children(|builder| {
+                builder.spawn(
+                    TextBundle::from_section(
+                        "Gates ",
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        margin: UiRect {
+                            right: Val::Px(24.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                );
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            margin: UiRect {
+                                right: Val::Px(24.0),
+                                ..default()
+                            },
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: get_button_color(*menu_options.gate_rules == GateRules::Penalty),
+                        ..default()
+                    },
+                    MainMenuItem::GatesPenalty,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Penalty",
+                        text_style.clone(),
+                    ));
+                });
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: get_button_color(*menu_options.gate_rules == GateRules::Strict),
+                        ..default()
+                    },
+                    MainMenuItem::GatesStrict,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Strict",
+                        text_style.clone(),
+                    ));
+                });
+            });
+
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    position: UiRect {
+                        top: Val::Px(290.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            }).with_children(|builder| {
+                builder.spawn(
+                    TextBundle::from_section(
+                        "Skiing ",
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        margin: UiRect {
+                            right: Val::Px(24.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                );
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            margin: UiRect {
+                                right: Val::Px(24.0),
+                                ..default()
+                            },
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: get_button_color(*menu_options.ski_profile == SkiProfile::Physics),
+                        ..default()
+                    },
+                    MainMenuItem::SkiPhysics,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Physics",
+                        text_style.clone(),
+                    ));
+                });
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: get_button_color(*menu_options.ski_profile == SkiProfile::Arcade),
+                        ..default()
+                    },
+                    MainMenuItem::SkiArcade,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Arcade",
+                        text_style.clone(),
+                    ));
+                });
+            });
+
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    position: UiRect {
+                        top: Val::Px(300.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            }).with_children(|builder| {
+                builder.spawn(
+                    TextBundle::from_section(
+                        "Yeti ",
+                        text_style.clone(),
+                    )
+                    .with_style(Style {
+                        margin: UiRect {
+                            right: Val::Px(24.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                );
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            margin: UiRect {
+                                right: Val::Px(24.0),
+                                ..default()
+                            },
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    MainMenuItem::YetiDifficulty,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            menu_options.yeti_settings.difficulty.name(),
+                            text_style.clone(),
+                        ),
+                        DifficultyText,
+                    ));
+                });
+
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: get_button_color(menu_options.yeti_settings.rubber_banding),
                         ..default()
                     },
-                    MainMenuItem::ControlDesktop,
+                    MainMenuItem::RubberBanding,
                 ))
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
-                        "Desktop",
+                        "Catch-up",
                         text_style.clone(),
                     ));
                 });
@@ -373,7 +1067,7 @@ fn setup(
 
             builder.spawn((
                 TextBundle::from_section(
-                    "Press M to toggle music",
+                    format!("Press {} to toggle music", key_name(key_bindings.key(Action::ToggleMusic))),
                     text_style.clone(),
                 )
                 .with_text_alignment(text_alignment)
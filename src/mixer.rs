@@ -0,0 +1,200 @@
+use bevy::{prelude::*, ecs::system::SystemParam};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    controls::{Action, KeyBindings},
+    leaderboard::NameEntry,
+    storage::{GameStorage, Storage, load_ron, save_ron},
+};
+
+const MIXER_KEY: &str = "audio";
+/// The music track is mastered much louder than the sound effects.
+const MUSIC_HEADROOM: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 3] = [Channel::Master, Channel::Music, Channel::Sfx];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::Master => "Master",
+            Channel::Music => "Music",
+            Channel::Sfx => "Sounds",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelLevel {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for ChannelLevel {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl ChannelLevel {
+    fn gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.volume }
+    }
+}
+
+/// Volume and mute of every channel, the master channel scales the other two.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioMixer {
+    pub master: ChannelLevel,
+    pub music: ChannelLevel,
+    pub sfx: ChannelLevel,
+}
+
+impl AudioMixer {
+    pub fn channel(&self, channel: Channel) -> &ChannelLevel {
+        match channel {
+            Channel::Master => &self.master,
+            Channel::Music => &self.music,
+            Channel::Sfx => &self.sfx,
+        }
+    }
+
+    pub fn channel_mut(&mut self, channel: Channel) -> &mut ChannelLevel {
+        match channel {
+            Channel::Master => &mut self.master,
+            Channel::Music => &mut self.music,
+            Channel::Sfx => &mut self.sfx,
+        }
+    }
+
+    pub fn toggle_mute(&mut self, channel: Channel) {
+        let level = self.channel_mut(channel);
+        level.muted = !level.muted;
+    }
+
+    /// Playback volume of a channel once the master channel is applied.
+    pub fn volume(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Master => self.master.gain(),
+            Channel::Music => self.master.gain() * self.music.gain() * MUSIC_HEADROOM,
+            Channel::Sfx => self.master.gain() * self.sfx.gain(),
+        }
+    }
+
+    pub fn load(storage: &dyn Storage) -> Self {
+        load_ron(storage, MIXER_KEY)
+    }
+
+    pub fn save(&self, storage: &dyn Storage) {
+        save_ron(storage, MIXER_KEY, self);
+    }
+}
+
+/// Plays audio through the mixer, every sound of the game goes through here.
+#[derive(SystemParam)]
+pub struct MixedAudio<'w> {
+    audio: Res<'w, Audio>,
+    audio_sinks: Res<'w, Assets<AudioSink>>,
+    mixer: Res<'w, AudioMixer>,
+}
+
+impl MixedAudio<'_> {
+    /// Plays a sound effect once, nothing is played while the channel is silent.
+    pub fn play_sfx(&self, source: Handle<AudioSource>) {
+        let volume = self.mixer.volume(Channel::Sfx);
+        if volume > 0.0 {
+            self.audio.play_with_settings(source, PlaybackSettings::ONCE.with_volume(volume));
+        }
+    }
+
+    /// Loops a track on the music channel, the returned sink follows the mixer while it is in a `MixedSink`.
+    pub fn play_music(&self, source: Handle<AudioSource>) -> Handle<AudioSink> {
+        let volume = self.mixer.volume(Channel::Music);
+        self.audio_sinks.get_handle(self.audio.play_with_settings(source, PlaybackSettings::LOOP.with_volume(volume)))
+    }
+}
+
+/// Long running sink whose volume tracks a mixer channel.
+#[derive(Component)]
+pub struct MixedSink {
+    pub sink: Handle<AudioSink>,
+    pub channel: Channel,
+}
+
+pub struct MixerPlugin;
+
+impl Plugin for MixerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameStorage>()
+            .init_resource::<AudioMixer>()
+            .add_startup_system(load_mixer)
+            .add_systems(
+                (
+                    mute_input
+                        .run_if(not(in_state(GameState::Controls)))
+                        .run_if(not(resource_exists::<NameEntry>())),
+                    save_mixer,
+                    update_sinks,
+                )
+            );
+    }
+}
+
+fn load_mixer(
+    mut commands: Commands,
+    storage: Res<GameStorage>,
+) {
+    commands.insert_resource(AudioMixer::load(storage.0.as_ref()));
+}
+
+fn mute_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    mut mixer: ResMut<AudioMixer>,
+) {
+    if key_bindings.just_pressed(&keyboard_input, Action::ToggleMusic) {
+        mixer.toggle_mute(Channel::Music);
+    }
+    if key_bindings.just_pressed(&keyboard_input, Action::ToggleSfx) {
+        mixer.toggle_mute(Channel::Sfx);
+    }
+}
+
+fn save_mixer(
+    mixer: Res<AudioMixer>,
+    storage: Res<GameStorage>,
+) {
+    if mixer.is_changed() && !mixer.is_added() {
+        mixer.save(storage.0.as_ref());
+    }
+}
+
+/// Sinks only exist once their audio has loaded, so they are checked every frame.
+fn update_sinks(
+    mixer: Res<AudioMixer>,
+    sink_q: Query<&MixedSink>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    for mixed in sink_q.iter() {
+        let Some(sink) = audio_sinks.get(&mixed.sink) else {
+            continue;
+        };
+        let volume = mixer.volume(mixed.channel);
+        if sink.volume() != volume {
+            sink.set_volume(volume);
+        }
+    }
+}
@@ -1,48 +1,24 @@
 use bevy::{prelude::*};
 
-#[derive(Resource)]
-pub struct MusicResource {
-    pub controller: Handle<AudioSink>,
-    pub playing: bool
-}
+use crate::mixer::{Channel, MixedAudio, MixedSink};
 
 pub struct MusicPlugin;
 
 impl Plugin for MusicPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_startup_system(setup)
-            .add_system(music_control);
+            .add_startup_system(setup);
     }
 }
 
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
-    audio_sinks: Res<Assets<AudioSink>>
+    audio: MixedAudio,
 ) {
     let audio_handle = asset_server.load("music.ogg");
-    let music_controller = audio_sinks.get_handle(audio.play_with_settings(
-        audio_handle,
-        PlaybackSettings::LOOP.with_volume(0.25),
-    ));
-
-    commands.insert_resource(MusicResource {
-        controller: music_controller,
-        playing: true,
+    commands.spawn(MixedSink {
+        sink: audio.play_music(audio_handle),
+        channel: Channel::Music,
     });
 }
-
-fn music_control(
-    mut music_resource: ResMut<MusicResource>,
-    keyboard_input: Res<Input<KeyCode>>,
-    audio_sinks: Res<Assets<AudioSink>>,
-) {
-    if keyboard_input.just_pressed(KeyCode::M) {
-        if let Some(sink) = audio_sinks.get(&music_resource.controller) {
-            sink.toggle();
-            music_resource.playing = !music_resource.playing;
-        }
-    }
-}
\ No newline at end of file
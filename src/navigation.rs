@@ -0,0 +1,157 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Reverse;
+
+use bevy::{prelude::*};
+
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (1, 0), (-1, 0), (0, 1), (0, -1),
+    (1, 1), (1, -1), (-1, 1), (-1, -1),
+];
+
+type Cell = (isize, isize);
+
+/// Coarse walkability grid over a part of the course, blocked cells are the ones covered by obstacles.
+pub struct NavGrid {
+    origin: Vec2,
+    cell_size: f32,
+    width: isize,
+    height: isize,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    /// Every obstacle in `obstacles` is grown by `clearance` so the walker's own size is accounted for.
+    pub fn new(area: Rect, cell_size: f32, clearance: Vec2, obstacles: impl IntoIterator<Item = Rect>) -> Self {
+        let width = (area.width() / cell_size).ceil().max(1.0) as isize;
+        let height = (area.height() / cell_size).ceil().max(1.0) as isize;
+        let mut grid = Self {
+            origin: area.min,
+            cell_size,
+            width,
+            height,
+            blocked: vec![false; (width * height) as usize],
+        };
+        for bounds in obstacles {
+            let (min_x, min_y) = grid.to_cell(bounds.min - clearance);
+            let (max_x, max_y) = grid.to_cell(bounds.max + clearance);
+            for x in min_x.max(0)..=max_x.min(width - 1) {
+                for y in min_y.max(0)..=max_y.min(height - 1) {
+                    grid.blocked[(y * width + x) as usize] = true;
+                }
+            }
+        }
+        grid
+    }
+
+    fn to_cell(&self, position: Vec2) -> Cell {
+        let cell = (position - self.origin) / self.cell_size;
+        (cell.x.floor() as isize, cell.y.floor() as isize)
+    }
+
+    fn to_position(&self, (x, y): Cell) -> Vec2 {
+        self.origin + (Vec2::new(x as f32, y as f32) + 0.5) * self.cell_size
+    }
+
+    fn contains(&self, (x, y): Cell) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    fn is_blocked(&self, cell: Cell) -> bool {
+        !self.contains(cell) || self.blocked[(cell.1 * self.width + cell.0) as usize]
+    }
+
+    pub fn is_walkable(&self, position: Vec2) -> bool {
+        !self.is_blocked(self.to_cell(position))
+    }
+
+    /// Samples the segment at quarter cell steps, true when no blocked cell is crossed.
+    pub fn line_of_sight(&self, start: Vec2, end: Vec2) -> bool {
+        let steps = ((end - start).length() / (self.cell_size / 4.0)).ceil() as usize;
+        (0..=steps).all(|step| {
+            let t = if steps == 0 { 0.0 } else { step as f32 / steps as f32 };
+            self.is_walkable(start.lerp(end, t))
+        })
+    }
+
+    /// A* from `start` to `goal`, the returned waypoints are smoothed and end at `goal`.
+    /// Both ends count as walkable, the walker may stand next to an obstacle and so may the target.
+    pub fn find_path(&self, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+        let start_cell = self.to_cell(start);
+        let goal_cell = self.to_cell(goal);
+        if !self.contains(start_cell) || !self.contains(goal_cell) {
+            return None;
+        }
+        let passable = |cell: Cell| cell == start_cell || cell == goal_cell || !self.is_blocked(cell);
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<Cell, Cell> = HashMap::new();
+        let mut costs: HashMap<Cell, u32> = HashMap::new();
+        costs.insert(start_cell, 0);
+        open.push(Reverse((heuristic(start_cell, goal_cell), start_cell)));
+
+        while let Some(Reverse((_, cell))) = open.pop() {
+            if cell == goal_cell {
+                return Some(self.smooth(start, goal, reconstruct(&came_from, cell)));
+            }
+            let cost = costs[&cell];
+            for (dx, dy) in NEIGHBOURS {
+                let next = (cell.0 + dx, cell.1 + dy);
+                if !passable(next) {
+                    continue;
+                }
+                let diagonal = dx != 0 && dy != 0;
+                // no squeezing between two obstacles touching at a corner
+                if diagonal && (!passable((cell.0 + dx, cell.1)) || !passable((cell.0, cell.1 + dy))) {
+                    continue;
+                }
+                let next_cost = cost + if diagonal { DIAGONAL_COST } else { STRAIGHT_COST };
+                if costs.get(&next).is_some_and(|&known| known <= next_cost) {
+                    continue;
+                }
+                costs.insert(next, next_cost);
+                came_from.insert(next, cell);
+                open.push(Reverse((next_cost + heuristic(next, goal_cell), next)));
+            }
+        }
+        None
+    }
+
+    /// Drops every waypoint that can be skipped with a straight line.
+    fn smooth(&self, start: Vec2, goal: Vec2, cells: Vec<Cell>) -> Vec<Vec2> {
+        let mut points: Vec<Vec2> = cells.into_iter().skip(1).map(|cell| self.to_position(cell)).collect();
+        points.pop();
+        points.push(goal);
+
+        let mut waypoints = vec![];
+        let mut from = start;
+        let mut index = 0;
+        while index < points.len() {
+            let mut furthest = index;
+            while furthest + 1 < points.len() && self.line_of_sight(from, points[furthest + 1]) {
+                furthest += 1;
+            }
+            from = points[furthest];
+            waypoints.push(from);
+            index = furthest + 1;
+        }
+        waypoints
+    }
+}
+
+fn heuristic(from: Cell, to: Cell) -> u32 {
+    let dx = (from.0 - to.0).unsigned_abs() as u32;
+    let dy = (from.1 - to.1).unsigned_abs() as u32;
+    STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+}
+
+fn reconstruct(came_from: &HashMap<Cell, Cell>, mut cell: Cell) -> Vec<Cell> {
+    let mut cells = vec![cell];
+    while let Some(&previous) = came_from.get(&cell) {
+        cells.push(previous);
+        cell = previous;
+    }
+    cells.reverse();
+    cells
+}
@@ -1,24 +1,25 @@
 use std::f32::consts::PI;
 
 use bevy::{prelude::*, math::vec2, utils::HashSet};
-use rand::{self, Rng, seq::SliceRandom};
+use rand::{Rng, seq::SliceRandom, rngs::StdRng};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     collidable::{Collidable, CollidableMovable},
     Alive,
     GameState,
+    RaceSchedule,
     SCALE_FACTOR,
     GameResources,
     player::{
         Player,
-        Falldown,
-        FALL_TIMEOUT,
-        PLAYER_Z_INDEX, LeftSki, RightSki, self, Catched, Slowdown
+        PLAYER_Z_INDEX, self, Grabbed, SkisQuery, Velocity, RaceClock, fall_down
     },
+    jump::{Airborne, Ramp, spawn_ramp},
     cleanup,
     despawn,
     debug::DebugMarker,
-    SPRITE_SIZE, yeti::{Yeti, YETI_STUN_TIME}, animation::{Animation, AnimateRotation}, stuneffect::{Stun, StunEffect}
+    SPRITE_SIZE, yeti::{Yeti, YetiState}, animation::{Animation, AnimateRotation}, stuneffect::{Stun, StunEffect}, simulation::SimulationSet, spatialtree::{SpatialTree, index_obstacles}
 };
 
 pub const TREE_COLLIDABLE_DIMENSIONS: (f32, f32) = (2.0 * SCALE_FACTOR, 2.0 * SCALE_FACTOR);
@@ -33,10 +34,11 @@ pub const REGION_OFFSETS: [(isize, isize); 5] = [
 pub const GAP_RANGE: (f32, f32) = (SPRITE_SIZE * SCALE_FACTOR * 1.0, SPRITE_SIZE * SCALE_FACTOR * 5.0);
 pub const DIFFICULTY_RAMPUP: f32 = 0.03;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ObstacleType {
     Stone,
     Tree,
+    Ramp,
 }
 
 struct TestSpawner {
@@ -55,17 +57,17 @@ impl TestSpawner {
     }
 }
 
-struct XYFillSpawner {
+struct XYFillSpawner<R: Rng> {
     region_width: f32,
     region_height: f32,
     current_filled_x: f32,
     current_filled_y: f32,
     y_gap: f32,
+    rng: R,
 }
 
-impl XYFillSpawner {
-    fn new(region_width: f32, region_height: f32) -> Self {
-        let mut rng = rand::thread_rng();
+impl<R: Rng> XYFillSpawner<R> {
+    fn new(region_width: f32, region_height: f32, mut rng: R) -> Self {
         let y_gap = rng.gen_range(GAP_RANGE.0..GAP_RANGE.1);
         let current_filled_y = y_gap * 2.0;
         Self {
@@ -74,26 +76,29 @@ impl XYFillSpawner {
             current_filled_x: rng.gen_range(GAP_RANGE.0..GAP_RANGE.1),
             current_filled_y,
             y_gap,
+            rng,
         }
     }
 }
 
-struct TileSpawner {
+struct TileSpawner<R: Rng> {
     tiles_count: usize,
     width: usize,
     act_tile: usize,
     tile_size: f32,
     spawn_offset: (f32, f32),
-    spawn_chance: f32
+    spawn_chance: f32,
+    rng: R,
 }
 
-impl TileSpawner {
+impl<R: Rng> TileSpawner<R> {
     fn new(
         tiles_count: usize,
         width: usize,
         tile_size: f32,
         spawn_offset: (f32, f32),
-        spawn_chance: f32
+        spawn_chance: f32,
+        rng: R,
     ) -> Self {
         Self {
             tiles_count,
@@ -101,7 +106,8 @@ impl TileSpawner {
             act_tile: 0,
             tile_size,
             spawn_offset,
-            spawn_chance
+            spawn_chance,
+            rng,
         }
     }
 }
@@ -118,11 +124,11 @@ impl Iterator for TestSpawner {
     }
 }
 
-impl Iterator for TileSpawner {
+impl<R: Rng> Iterator for TileSpawner<R> {
     type Item = (ObstacleType, f32, f32);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut rng = rand::thread_rng();
+        let rng = &mut self.rng;
         let mut spawn_chance = rng.gen_range(0.0..1.0);
 
         while spawn_chance > self.spawn_chance {
@@ -140,7 +146,7 @@ impl Iterator for TileSpawner {
         let x = (self.act_tile % self.width) as f32 * self.tile_size + rng.gen_range(-self.spawn_offset.0..self.spawn_offset.0);
         let y = (self.act_tile / self.width) as f32 * self.tile_size + rng.gen_range(-self.spawn_offset.1..self.spawn_offset.1);
         let choices = [ObstacleType::Tree, ObstacleType::Stone];
-        let Some(obstacle_type) = choices.choose(&mut rng) else {
+        let Some(obstacle_type) = choices.choose(rng) else {
             return None;
         };
         self.act_tile += 1;
@@ -148,16 +154,16 @@ impl Iterator for TileSpawner {
     }
 }
 
-impl Iterator for XYFillSpawner {
+impl<R: Rng> Iterator for XYFillSpawner<R> {
     type Item = (ObstacleType, f32, f32);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.current_filled_y > self.region_height {
             return None;
         }
-        let mut rng = rand::thread_rng();
+        let rng = &mut self.rng;
         let choices = [ObstacleType::Tree, ObstacleType::Stone];
-        let Some(obstacle_type) = choices.choose(&mut rng) else {
+        let Some(obstacle_type) = choices.choose(rng) else {
             return None;
         };
 
@@ -180,11 +186,15 @@ pub struct ObstacleSpawner {
     last_processed_player_region_y: Option<isize>,
     last_processed_player_region_x: Option<isize>,
     spawned_regions: HashSet<(isize, isize)>,
+    rng: StdRng,
 }
 
 #[derive(Component)]
 pub struct Obstacle;
 
+/// Obstacles that stop a yeti, ramps are run over.
+pub type YetiObstacles = (With<Obstacle>, Without<Yeti>, Without<Ramp>);
+
 pub struct ObstaclePlugin;
 
 impl Plugin for ObstaclePlugin {
@@ -194,18 +204,23 @@ impl Plugin for ObstaclePlugin {
             //     last_processed_player_region_y: None,
             //     last_processed_player_region_x: None,
             //     spawned_regions: HashSet::new(),
+            //     rng: StdRng::seed_from_u64(0),
             // })
-            .add_system(despawn::<Obstacle>.in_schedule(OnExit(GameState::GameOver)))
-            .add_system(reset_spawner.in_schedule(OnExit(GameState::GameOver)))
+            .add_system(despawn::<Obstacle>.in_schedule(RaceSchedule::Teardown))
+            .add_system(reset_spawner.in_schedule(RaceSchedule::Teardown))
+            .add_system(index_obstacles.in_set(SimulationSet::Begin).in_schedule(CoreSchedule::FixedUpdate))
             .add_systems(
                 (
-                    spawn_obstacles,
-                    update_collidables.after(player::update_player),
+                    update_collidables.after(player::update_movables),
                     process_collisions_player.after(update_collidables),
                     process_collisions_yeti.after(update_collidables),
+                ).in_set(SimulationSet::Tick).in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_systems(
+                (
+                    spawn_obstacles,
                     cleanup::<Obstacle>,
                     cleanup_regions.after(spawn_obstacles),
-                    // cleanup_spatial_tree
                 ).in_set(OnUpdate(GameState::Playing))
             )
             ;
@@ -257,53 +272,65 @@ pub fn spawn_obstacles(
             width,
             tile_size,
             (15., 15.),
-            (camera_region_y.abs() as f32).log2() * DIFFICULTY_RAMPUP);
-        // let spawner = SpawnerType::XYGaps => XYFillSpawner::new(region_width, region_height);
+            (camera_region_y.abs() as f32).log2() * DIFFICULTY_RAMPUP,
+            &mut spawner_r.rng);
+        // let spawner = SpawnerType::XYGaps => XYFillSpawner::new(region_width, region_height, &mut spawner_r.rng);
         for (obstacle_type, ox, oy) in spawner {
             let x = (rx as f32 - 0.5) * region_width + ox;
             let y = ry as f32 * region_height - oy;
 
-            let (sprite_rect, collidable_dimension, offsets, offset_z) = match obstacle_type {
-                ObstacleType::Tree => (game_resources.tree, TREE_COLLIDABLE_DIMENSIONS, TREE_COLLIDABLE_OFFSETS, 0.2),
-                ObstacleType::Stone => (game_resources.stone, STONE_COLLIDABLE_DIMENSIONS, STONE_COLLIDABLE_OFFSETS, 0.1)
-            };
-
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
-                        rect: Some(sprite_rect.clone()),
-                        ..default()
-                    },
-                    texture: game_resources.image_handle.clone(),
-                    transform: Transform::from_xyz(x, y, PLAYER_Z_INDEX + 1.0 + offset_z),
-                    ..default()
-                },
-                Collidable::new(x, y, collidable_dimension.0, collidable_dimension.1, offsets.0, offsets.1),
-                Alive,
-                Obstacle,
-            ))
-            .with_children(|parent| {
-                parent.spawn((
-                    SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::rgb(0.75, 0.25, 0.25),
-                            custom_size: Some(Vec2::new(collidable_dimension.0 * 2.0, collidable_dimension.1 * 2.0)),
-                            ..default()
-                        },
-                        transform: Transform::from_xyz(offsets.0, offsets.1, 2.),
-                        visibility: Visibility::Hidden,
-                        ..default()
-                    },
-                    DebugMarker
-                ));
-            })
-            ;
+            spawn_obstacle(&mut commands, &game_resources, &obstacle_type, x, y);
         }
         spawner_r.spawned_regions.insert((rx, ry));
     }
 }
 
+pub fn spawn_obstacle(
+    commands: &mut Commands,
+    game_resources: &GameResources,
+    obstacle_type: &ObstacleType,
+    x: f32,
+    y: f32,
+) -> Entity {
+    let (sprite_rect, collidable_dimension, offsets, offset_z) = match obstacle_type {
+        ObstacleType::Tree => (game_resources.tree, TREE_COLLIDABLE_DIMENSIONS, TREE_COLLIDABLE_OFFSETS, 0.2),
+        ObstacleType::Stone => (game_resources.stone, STONE_COLLIDABLE_DIMENSIONS, STONE_COLLIDABLE_OFFSETS, 0.1),
+        ObstacleType::Ramp => return spawn_ramp(commands, x, y),
+    };
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
+                rect: Some(sprite_rect.clone()),
+                ..default()
+            },
+            texture: game_resources.image_handle.clone(),
+            transform: Transform::from_xyz(x, y, PLAYER_Z_INDEX + 1.0 + offset_z),
+            ..default()
+        },
+        Collidable::new(x, y, collidable_dimension.0, collidable_dimension.1, offsets.0, offsets.1),
+        Alive,
+        Obstacle,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.75, 0.25, 0.25),
+                    custom_size: Some(Vec2::new(collidable_dimension.0 * 2.0, collidable_dimension.1 * 2.0)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(offsets.0, offsets.1, 2.),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DebugMarker
+        ));
+    })
+    .id()
+}
+
 pub fn cleanup_regions(
     camera_q: Query<&Transform, With<Camera>>,
     window: Query<&Window>,
@@ -346,86 +373,95 @@ pub fn update_collidables(
 pub fn process_collisions_player(
     mut commands: Commands,
     game_resources: Res<GameResources>,
-    mut player_q: Query<(Entity, &mut Sprite, &Collidable, &Children), (With<Player>, With<Alive>, Without<Obstacle>)>,
-    mut skis_q: Query<&mut Visibility, Or<(With<LeftSki>, With<RightSki>)>>,
-    obstacles_q: Query<&Collidable, (With<Obstacle>, Without<Player>)>
+    mut player_q: Query<(Entity, &Player, &mut Sprite, &Collidable, &Children), (With<Alive>, Without<Obstacle>, Without<Airborne>)>,
+    mut skis_q: SkisQuery,
+    obstacles_q: Query<(&Collidable, Option<&Ramp>), (With<Obstacle>, Without<Player>)>,
+    spatial_tree: Res<SpatialTree>,
 ) {
     let Ok((
         entity,
+        player,
         mut sprite,
         collidable_player,
         children
     )) = player_q.get_single_mut() else {
         return;
     };
-    let has_collided = obstacles_q.iter().any(|collidable_obstacle| {
-        collidable_obstacle.intersect(&collidable_player)
-    });
-
-    if has_collided {
-        for &ch in children {
-            if let Ok(mut visibility) = skis_q.get_mut(ch) {
-                *visibility = Visibility::Hidden;
-            }
-        }
-        commands.entity(entity).insert(Slowdown(Timer::from_seconds(FALL_TIMEOUT, TimerMode::Once)));
-        commands.entity(entity).insert(Falldown);
-        sprite.rect = Some(game_resources.fall_down);
+    let (ramps, obstacles): (Vec<_>, Vec<_>) = spatial_tree.get_at(collidable_player.bounds()).into_iter()
+        .filter_map(|entity| obstacles_q.get(entity).ok())
+        .filter(|(collidable_obstacle, _)| collidable_obstacle.intersect(collidable_player))
+        .partition(|(_, ramp)| ramp.is_some());
+
+    if !obstacles.is_empty() {
+        fall_down(&mut commands, &game_resources, entity, &mut sprite, children, &mut skis_q);
+    } else if !ramps.is_empty() {
+        commands.entity(entity).insert(Airborne::new(player.speed()));
     }
 }
 
 pub fn process_collisions_yeti(
     mut commands: Commands,
     game_resources: Res<GameResources>,
-    mut yeti_q: Query<(Entity, &mut Animation, &Yeti, &Collidable), (With<Yeti>, With<Alive>, Without<Obstacle>, Without<Stun>)>,
-    player_q: Query<(Entity, &Collidable), (With<Player>, With<Alive>, Without<Obstacle>, Without<Yeti>)>,
-    obstacles_q: Query<&Collidable, (With<Obstacle>, Without<Yeti>)>,
+    mut yeti_q: Query<(Entity, &mut Animation, &mut Yeti, &Collidable), (With<Alive>, Without<Obstacle>, Without<Stun>)>,
+    mut player_q: Query<(Entity, &Collidable, &mut Velocity, &mut Visibility, &mut RaceClock), (With<Player>, With<Alive>, Without<Obstacle>, Without<Yeti>)>,
+    obstacles_q: Query<&Collidable, YetiObstacles>,
+    spatial_tree: Res<SpatialTree>,
 ) {
-    let Ok((
+    let mut player = player_q.get_single_mut().ok();
+    for (
         entity_yeti,
         mut animation,
-        yeti,
+        mut yeti,
         collidable_yeti
-    )) = yeti_q.get_single_mut() else {
-        return;
-    };
-    if !yeti.ignore_collisions.finished() {
-        return;
-    }
-    let has_collided = obstacles_q.iter().any(|collidable_obstacle| {
-        collidable_obstacle.intersect(&collidable_yeti)
-    });
-    if has_collided {
-        animation.set_frames(vec![game_resources.yeti_fallen]);
-        let stun_child = commands.spawn((
-            SpriteBundle {
-                sprite: Sprite {
-                    custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
-                    rect: Some(game_resources.stun),
+    ) in yeti_q.iter_mut() {
+        if !yeti.ignore_collisions.finished() || yeti.state() == YetiState::Eat {
+            continue;
+        }
+        let has_collided = spatial_tree.get_at(collidable_yeti.bounds()).into_iter()
+            .filter_map(|entity| obstacles_q.get(entity).ok())
+            .any(|collidable_obstacle| collidable_obstacle.intersect(collidable_yeti));
+        if has_collided {
+            animation.set_frames(vec![game_resources.yeti_fallen]);
+            let stun_child = commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
+                        rect: Some(game_resources.stun),
+                        ..default()
+                    },
+                    texture: game_resources.image_handle.clone(),
+                    transform: Transform::from_xyz(0.0, 7.0 * SCALE_FACTOR, 0.5),
                     ..default()
                 },
-                texture: game_resources.image_handle.clone(),
-                transform: Transform::from_xyz(0.0, 7.0 * SCALE_FACTOR, 0.5),
-                ..default()
-            },
-            AnimateRotation {
-                angular_vel: PI
-            },
-            StunEffect
-        )).id();
-        commands.entity(entity_yeti).push_children(&[stun_child]);
-        commands.entity(entity_yeti).insert(Stun(Timer::from_seconds(YETI_STUN_TIME, TimerMode::Once)));
-        return;
-    }
+                AnimateRotation {
+                    angular_vel: PI
+                },
+                StunEffect
+            )).id();
+            commands.entity(entity_yeti).push_children(&[stun_child]);
+            commands.entity(entity_yeti).insert(Stun(Timer::from_seconds(yeti.stun_time, TimerMode::Once)));
+            continue;
+        }
 
-    let Ok((
-        entity_player,
-        collidable_player
-    )) = player_q.get_single() else {
-        return;
-    };
-    if collidable_player.intersect(&collidable_yeti) {
-        commands.entity(entity_player).remove::<Alive>();
-        commands.entity(entity_player).insert(Catched);
+        let Some((
+            entity_player,
+            collidable_player,
+            velocity,
+            visibility,
+            clock,
+        )) = player.as_mut() else {
+            continue;
+        };
+        if collidable_player.intersect(collidable_yeti) {
+            // the skier disappears in the yeti's hands, the eating frames show them
+            velocity.0 = Vec2::ZERO;
+            **visibility = Visibility::Hidden;
+            clock.stop();
+            yeti.set_state(YetiState::Eat);
+            commands.entity(*entity_player).remove::<Alive>();
+            commands.entity(*entity_player).insert(Grabbed);
+            // only one of the pack gets the meal
+            player = None;
+        }
     }
 }
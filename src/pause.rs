@@ -0,0 +1,165 @@
+use bevy::{prelude::*, window::WindowFocused};
+
+use crate::{
+    GameState,
+    GameResources,
+    despawn,
+    gamepad::ButtonPresses,
+    NORMAL_BUTTON,
+    controls::{Action, KeyBindings},
+    replay::ReplayPlayback,
+};
+
+/// Marks the next switch between `Playing` and `Paused` as a pause or a resume, the race is neither set up nor torn down.
+#[derive(Resource)]
+pub struct PauseTransition;
+
+#[derive(Component)]
+struct PauseElement;
+
+#[derive(Component)]
+enum PauseControl {
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(pause_input.run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))))
+            .add_system(pause_on_focus_lost.in_set(OnUpdate(GameState::Playing)))
+            .add_system(setup_pause.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(controls_interaction.in_set(OnUpdate(GameState::Paused)))
+            .add_system(despawn::<PauseElement>.in_schedule(OnExit(GameState::Paused)));
+    }
+}
+
+/// Switches between `Playing` and `Paused` without restarting the race.
+pub fn toggle_pause(
+    commands: &mut Commands,
+    app_state: &mut NextState<GameState>,
+    current: GameState,
+) {
+    let next = match current {
+        GameState::Playing => GameState::Paused,
+        GameState::Paused => GameState::Playing,
+        _ => return,
+    };
+    commands.insert_resource(PauseTransition);
+    app_state.set(next);
+}
+
+fn pause_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    state: Res<State<GameState>>,
+    mut app_state: ResMut<NextState<GameState>>,
+) {
+    // P pauses too, unless it was bound to something else
+    let pressed = key_bindings.just_pressed(&keyboard_input, Action::Pause)
+        || (keyboard_input.just_pressed(KeyCode::P) && key_bindings.action(KeyCode::P).is_none())
+        || gamepads.iter().any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start)));
+    if pressed {
+        toggle_pause(&mut commands, &mut app_state, state.0);
+    }
+}
+
+fn pause_on_focus_lost(
+    mut commands: Commands,
+    mut ev_focused: EventReader<WindowFocused>,
+    mut app_state: ResMut<NextState<GameState>>,
+) {
+    if ev_focused.iter().any(|event| !event.focused) {
+        toggle_pause(&mut commands, &mut app_state, GameState::Playing);
+    }
+}
+
+fn controls_interaction(
+    mut presses: ButtonPresses<PauseControl>,
+    mut commands: Commands,
+    mut app_state: ResMut<NextState<GameState>>,
+) {
+    for control in presses.iter() {
+        match control {
+            PauseControl::Resume => {
+                toggle_pause(&mut commands, &mut app_state, GameState::Paused);
+            },
+            PauseControl::Restart => {
+                app_state.set(GameState::Playing);
+            },
+            PauseControl::QuitToMenu => {
+                commands.remove_resource::<ReplayPlayback>();
+                app_state.set(GameState::MainMenu);
+            },
+        }
+    }
+}
+
+fn setup_pause(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+) {
+    let text_style = TextStyle {
+        font: game_resources.font_handle.clone(),
+        font_size: 24.0,
+        color: Color::BLACK,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                    },
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    gap: Size::height(Val::Px(16.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.95, 0.95, 1.0, 0.7).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseElement,
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section("Paused", text_style.clone()));
+            for (control, label) in [
+                (PauseControl::Resume, "Resume"),
+                (PauseControl::Restart, "Restart"),
+                (PauseControl::QuitToMenu, "Quit to Menu"),
+            ] {
+                builder.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect {
+                                left: Val::Px(12.0),
+                                right: Val::Px(12.0),
+                                top: Val::Px(8.0),
+                                bottom: Val::Px(8.0)
+                            },
+                            ..default()
+                        },
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    control,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(label, text_style.clone()));
+                });
+            }
+        });
+}
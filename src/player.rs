@@ -5,11 +5,16 @@ use crate::{
     GameResources,
     Alive,
     GameState,
+    RaceSchedule,
     collidable::{Collidable, CollidableMovable},
     despawn,
     debug::{DebugMarker},
     SCALE_FACTOR,
-    uicontrols::{UiControlType, self}, stuneffect::{Stun, StunEffect}, camera::CameraFocus, animation::AnimateRotation
+    uicontrols::{UiControlType, SkiStance, self}, stuneffect::{Stun, StunEffect}, camera::CameraFocus, animation::AnimateRotation,
+    simulation::{SimulationSet, Interpolated},
+    replay::ReplayPlayback,
+    jump::Airborne,
+    surface::{SurfaceType, MOGUL_WAVELENGTH},
 };
 
 
@@ -25,6 +30,13 @@ const ROTATION_SPEED: f32 = FRAC_PI_2 * 1.5;
 const ROTATION_HINDERANCE: f32 = FRAC_PI_8 / 2.0; //at faster speed the turning is harder, this can be later upgraded to be closer to zero
 const SPRITE_ROTATION_TRESHOLD: f32 = FRAC_PI_8 / 2.0;
 const ROTATION_HINDERANCE_SLOPE: f32 = 15.0;
+const SLOPE_GRAVITY: f32 = 30.0 * SCALE_FACTOR;
+const SNOW_FRICTION: f32 = 4.0 * SCALE_FACTOR;
+const EDGE_FRICTION: f32 = 14.0 * SCALE_FACTOR;
+const BRAKE_FRICTION: f32 = 40.0 * SCALE_FACTOR;
+// straight down the fall line the drag balances gravity at `SPEED`
+const AIR_DRAG: f32 = (SLOPE_GRAVITY - SNOW_FRICTION) / (SPEED * SPEED);
+const TUCK_DRAG_RATIO: f32 = 0.6;
 
 
 fn get_graphics_index(rotation: f32) -> Option<usize> {
@@ -92,23 +104,68 @@ pub struct Rotation(pub f32);
 pub struct Velocity(pub Vec2);
 
 
-#[derive(Component)]
+/// Points for the run, awarded per gate independently of the race time.
+#[derive(Component, Default)]
 pub struct Score {
-    value: f32
+    points: i32
 }
 
 impl Score {
+    pub fn points(&self) -> i32 {
+        self.points
+    }
     pub fn increase(&mut self) {
-        self.value = self.value + 10.0;
+        self.points += 10;
     }
     pub fn decrease(&mut self) {
-        self.value = self.value - 10.0;
+        self.points -= 10;
+    }
+}
+
+/// Race time in seconds, runs from the first movement until the finish line is crossed.
+#[derive(Component, Default)]
+pub struct RaceClock {
+    elapsed: f32,
+    penalty: f32,
+    started: bool,
+    stopped: bool,
+}
+
+impl RaceClock {
+    /// Elapsed time including penalties.
+    pub fn time(&self) -> f32 {
+        self.elapsed + self.penalty
+    }
+    pub fn add_penalty(&mut self, seconds: f32) {
+        self.penalty += seconds;
+    }
+    pub fn is_running(&self) -> bool {
+        self.started && !self.stopped
+    }
+    pub fn stop(&mut self) {
+        self.stopped = true;
     }
 }
 
+/// How speed is derived from the input and the ski direction.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq)]
+pub enum SkiProfile {
+    /// Momentum driven by gravity along the fall line, slowed by friction and air drag.
+    #[default]
+    Physics,
+    /// Speed follows the ski direction directly, turning snaps it down.
+    Arcade,
+}
+
 #[derive(Component)]
 pub struct Player {
     pub control_type: Option<UiControlType>,
+    /// How hard `control_type` turns, from 0 to 1, keys and touch buttons always turn fully.
+    pub steering: f32,
+    pub stance: SkiStance,
+    /// Surface patch under the skier, `None` is regular snow.
+    pub surface: Option<SurfaceType>,
+    surface_phase: f32,
     speed: f32,
     max_speed: f32,
     min_speed: f32,
@@ -119,6 +176,10 @@ impl Player {
     pub fn new(max_speed: f32, min_speed_ratio: f32, max_acceleration: f32) -> Self {
         Self {
             control_type: None,
+            steering: 0.0,
+            stance: SkiStance::Normal,
+            surface: None,
+            surface_phase: 0.0,
             speed: 0.0,
             max_speed,
             min_speed: max_speed * min_speed_ratio.min(0.95),
@@ -131,16 +192,60 @@ impl Player {
     }
 
     pub fn get_acceleration(&self, rotation: f32) -> f32 {
-        self.max_acceleration * rotation.cos().abs()
+        self.max_acceleration * rotation.cos().abs() * self.surface.map_or(1.0, |surface| surface.acceleration())
     }
 
     pub fn deaccelerate(&mut self, rotation: f32) {
-        self.speed = self.get_speed(rotation);
+        let speed = self.get_speed(rotation);
+        let speed_loss = self.surface.map_or(1.0, |surface| surface.turn_speed_loss());
+        self.speed = speed + (self.speed - speed).max(0.0) * (1.0 - speed_loss);
     }
 
     pub fn accelerate(&mut self, rotation: f32, delta: f32) {
         let act_acceleration = self.get_acceleration(rotation) * delta;
-        self.speed = (self.speed + act_acceleration).min(self.max_speed)
+        let max_speed = self.max_speed * self.surface.map_or(1.0, |surface| surface.top_speed());
+        self.speed = (self.speed + act_acceleration).min(max_speed)
+    }
+
+    /// Multiplier of the turning speed on the current surface.
+    pub fn grip(&self) -> f32 {
+        self.surface.map_or(1.0, |surface| surface.grip())
+    }
+
+    /// Advances over moguls and returns the multiplier of the speed for this tick.
+    pub fn wobble(&mut self, delta: f32) -> f32 {
+        let Some(surface) = self.surface else {
+            return 1.0;
+        };
+        self.surface_phase = (self.surface_phase + self.speed * delta / MOGUL_WAVELENGTH).fract();
+        1.0 + surface.wobble() * (self.surface_phase * 2.0 * PI).sin()
+    }
+
+    /// Position within the current mogul, from 0 to 1.
+    pub fn surface_phase(&self) -> f32 {
+        self.surface_phase
+    }
+
+    /// Speed is carried along the skis, `edging` is set while the skis are turning.
+    pub fn glide(&mut self, rotation: f32, edging: bool, delta: f32) {
+        let gravity = SLOPE_GRAVITY * rotation.cos();
+        let mut friction = SNOW_FRICTION;
+        if edging {
+            friction += EDGE_FRICTION;
+        }
+        friction *= self.surface.map_or(1.0, |surface| surface.friction());
+        let mut drag = AIR_DRAG;
+        match self.stance {
+            SkiStance::Tuck => drag *= TUCK_DRAG_RATIO,
+            SkiStance::Brake => friction += BRAKE_FRICTION,
+            SkiStance::Normal => {},
+        }
+        let acceleration = gravity - friction - drag * self.speed * self.speed;
+        self.speed = (self.speed + acceleration * delta).max(0.0);
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
     }
 }
 
@@ -167,70 +272,132 @@ pub struct RightSki;
 
 #[derive(Component)]
 pub struct Catched;
+
+/// Held by the yeti while it eats, becomes `Catched` once the meal is over.
+#[derive(Component)]
+pub struct Grabbed;
+
+/// Skiers that are in control, neither stunned nor held by the yeti.
+type SteerablePlayer = (Without<Stun>, Without<Grabbed>);
+
+pub type SkisQuery<'w, 's> = Query<'w, 's, &'static mut Visibility, Or<(With<LeftSki>, With<RightSki>)>>;
+
+/// Knocks the skier over, `update_slowdown` stuns them once the fall is over.
+pub fn fall_down(
+    commands: &mut Commands,
+    game_resources: &GameResources,
+    entity: Entity,
+    sprite: &mut Sprite,
+    children: &Children,
+    skis_q: &mut SkisQuery,
+) {
+    for &ch in children {
+        if let Ok(mut visibility) = skis_q.get_mut(ch) {
+            *visibility = Visibility::Hidden;
+        }
+    }
+    commands.entity(entity).insert(Slowdown(Timer::from_seconds(FALL_TIMEOUT, TimerMode::Once)));
+    commands.entity(entity).insert(Falldown);
+    sprite.rect = Some(game_resources.fall_down);
+}
+
+/// Missed a gate under `GateRules::Strict`.
+#[derive(Component)]
+pub struct Disqualified;
+
+/// A player who crossed the finish line without being disqualified, only such a run is kept.
+pub type FinishedPlayer = (With<Player>, With<CompletedRace>, Without<Disqualified>);
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_system(setup.in_schedule(OnEnter(GameState::Playing)))
+            .init_resource::<SkiProfile>()
+            .add_system(setup.in_schedule(RaceSchedule::Setup))
             .add_systems(
                 (
                     despawn::<Player>,
                     despawn::<ScoreText>,
-                ).in_schedule(OnExit(GameState::GameOver))
+                ).in_schedule(RaceSchedule::Teardown)
             )
             .add_systems(
                 (
                     update_player.after(uicontrols::player_input),
-                    update_graphics.after(update_player),
                     update_movables.after(update_player),
+                    update_race_clock.after(update_player).before(update_movables),
                     update_slowdown,
-                    update_fallover.after(update_player),
-                    gameover_detection.after(update_player),
-                    update_ski_rotation.after(update_player),
-                    update_score,
+                    update_score.after(update_race_clock),
+                ).in_set(SimulationSet::Tick).in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_systems(
+                (
+                    update_graphics,
+                    gameover_detection,
+                    update_ski_rotation,
                 ).in_set(OnUpdate(GameState::Playing))
             );
     }
 }
 
 pub fn update_player(
-    timer: Res<Time>,
-    mut player_q: Query<(&mut Velocity, &mut Rotation, &mut Player), Without<Stun>>,
+    fixed_time: Res<FixedTime>,
+    ski_profile: Res<SkiProfile>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut player_q: Query<(&mut Velocity, &mut Rotation, &mut Player, Option<&Airborne>), SteerablePlayer>,
 ) {
     let Ok((
         mut velocity,
         mut rotation,
-        mut player
+        mut player,
+        airborne
     )) = player_q.get_single_mut() else {
         return;
     };
 
-    let dt = timer.delta();
+    let dt = fixed_time.period;
     if let Some(control_type) = &player.control_type {
         let rot_hinderance = get_rotation_hinderance(player.speed);
+        let rot_speed = (ROTATION_SPEED - rot_hinderance) * player.grip();
         let rot_delta = match control_type {
-            UiControlType::Left => -(ROTATION_SPEED - rot_hinderance),
-            UiControlType::Right => ROTATION_SPEED - rot_hinderance,
+            UiControlType::Left => -rot_speed,
+            UiControlType::Right => rot_speed,
         };
-        rotation.0 = (rotation.0 + rot_delta * dt.as_secs_f32())
+        rotation.0 = (rotation.0 + rot_delta * player.steering * dt.as_secs_f32())
             .max(-FRAC_PI_2)
             .min(FRAC_PI_2);
-        player.deaccelerate(rotation.0);
-    } else {
-        player.accelerate(rotation.0, dt.as_secs_f32());
+    }
+
+    // in the air only the skis turn, the flight keeps the launch velocity
+    if airborne.is_some() {
+        player.control_type = None;
+        player.steering = 0.0;
+        player.stance = SkiStance::Normal;
+        return;
+    }
+
+    // a replay is simulated with the profile it was recorded with
+    let ski_profile = playback.map_or(*ski_profile, |playback| playback.ski_profile());
+    let edging = player.control_type.is_some();
+    match ski_profile {
+        SkiProfile::Physics => player.glide(rotation.0, edging, dt.as_secs_f32()),
+        SkiProfile::Arcade if edging => player.deaccelerate(rotation.0),
+        SkiProfile::Arcade => player.accelerate(rotation.0, dt.as_secs_f32()),
     }
 
     player.control_type = None;
+    player.steering = 0.0;
+    player.stance = SkiStance::Normal;
     let act_rotation = rotation.0 - FRAC_PI_2; //0 degrees is pointing down (e.g. [0, -1], not to [1, 0])
-    velocity.0 = vec2(act_rotation.cos() * player.speed, act_rotation.sin() * player.speed);
+    let speed = player.speed * player.wobble(dt.as_secs_f32());
+    velocity.0 = vec2(act_rotation.cos() * speed, act_rotation.sin() * speed);
 }
 
 pub fn update_movables(
-    timer: Res<Time>,
+    fixed_time: Res<FixedTime>,
     mut movables_q: Query<(&mut Transform, &Velocity), Without<Stun>>,
 ) {
-    let dt = timer.delta();
+    let dt = fixed_time.period;
     for (mut transform, vel) in movables_q.iter_mut() {
         transform.translation.x += vel.0.x * dt.as_secs_f32();
         transform.translation.y += vel.0.y * dt.as_secs_f32();
@@ -274,74 +441,80 @@ fn update_graphics(
     sprite.flip_x = flip_x;
 }
 
+fn update_race_clock(
+    fixed_time: Res<FixedTime>,
+    mut player_q: Query<(&mut RaceClock, &Velocity), With<Player>>,
+) {
+    let Ok((mut clock, velocity)) = player_q.get_single_mut() else {
+        return;
+    };
+    if !clock.started && velocity.0 != Vec2::ZERO {
+        clock.started = true;
+    }
+    if clock.is_running() {
+        clock.elapsed += fixed_time.period.as_secs_f32();
+    }
+}
+
 fn update_score(
-    time: Res<Time>,
-    mut player_q: Query<&mut Score, With<Player>>,
+    player_q: Query<(&RaceClock, &Score), With<Player>>,
     mut text_q: Query<&mut Text, With<ScoreText>>,
 ) {
-    let Ok(mut score) = player_q.get_single_mut() else {
+    let Ok((clock, score)) = player_q.get_single() else {
         return;
     };
     let Ok(mut text) = text_q.get_single_mut() else {
         return;
     };
-    score.value += time.delta_seconds();
-    text.sections[0].value = format!("Time: {:.0}", score.value);
+    text.sections[0].value = format_result(clock, score);
+}
+
+pub fn format_result(clock: &RaceClock, score: &Score) -> String {
+    format!("Time: {:.3}  Points: {}", clock.time(), score.points())
 }
 
 fn update_slowdown(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
+    game_resources: Res<GameResources>,
     mut commands: Commands,
-    mut player_q: Query<(Entity, &mut Slowdown, &mut Player)>,
+    mut player_q: Query<(Entity, &mut Slowdown, &mut Player, Option<&Falldown>)>,
 ) {
-    let Ok((entity, mut slowdown, mut player)) = player_q.get_single_mut() else {
+    let Ok((entity, mut slowdown, mut player, falldown)) = player_q.get_single_mut() else {
         return;
     };
-    if !slowdown.0.tick(time.delta()).finished() {
+    if !slowdown.0.tick(fixed_time.period).finished() {
         return;
     }
 
     player.speed = 0.0;
     commands.entity(entity).remove::<Slowdown>();
-}
+    if falldown.is_none() {
+        return;
+    }
 
-fn update_fallover(
-    game_resources: Res<GameResources>,
-    mut stopped: RemovedComponents<Slowdown>,
-    mut commands: Commands,
-    mut player_q: Query<(Entity, &mut Player), With<Falldown>>,
-) {
-    for stopped in stopped.iter() {
-        let Ok((entity, mut player)) = player_q.get_mut(stopped) else {
-            continue;
-        };
-        player.speed = 0.0;
-        let stun_child = commands.spawn((
-            SpriteBundle {
-                sprite: Sprite {
-                    custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
-                    rect: Some(game_resources.stun),
-                    ..default()
-                },
-                texture: game_resources.image_handle.clone(),
-                transform: Transform::from_xyz(0.0, 4.0 * SCALE_FACTOR, 0.5),
+    let stun_child = commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
+                rect: Some(game_resources.stun),
                 ..default()
             },
-            AnimateRotation {
-                angular_vel: PI
-            },
-            StunEffect
-        )).id();
-        commands.entity(entity).remove::<Falldown>();
-        commands.entity(entity).push_children(&[stun_child]);
-        commands.entity(entity).insert(Stun(Timer::from_seconds(0.5, TimerMode::Once)));
-    }
-
-
+            texture: game_resources.image_handle.clone(),
+            transform: Transform::from_xyz(0.0, 4.0 * SCALE_FACTOR, 0.5),
+            ..default()
+        },
+        AnimateRotation {
+            angular_vel: PI
+        },
+        StunEffect
+    )).id();
+    commands.entity(entity).remove::<Falldown>();
+    commands.entity(entity).push_children(&[stun_child]);
+    commands.entity(entity).insert(Stun(Timer::from_seconds(0.5, TimerMode::Once)));
 }
 
 fn gameover_detection(
-    player_q: Query<Entity, (With<Player>, Without<Slowdown>, Or<(With<Catched>, With<CompletedRace>)>)>,
+    player_q: Query<Entity, (With<Player>, Without<Slowdown>, Or<(With<Catched>, With<CompletedRace>, With<Disqualified>)>)>,
     mut app_state: ResMut<NextState<GameState>>,
 ) {
     if let Ok(_) = player_q.get_single() {
@@ -379,6 +552,7 @@ pub fn setup(
             ..default()
         },
         CameraFocus,
+        Interpolated::new(Vec2::ZERO),
         Player::new(SPEED, 0.4, ACCELERATION),
         Alive,
         Velocity(Vec2::ZERO),
@@ -389,7 +563,8 @@ pub fn setup(
             PLAYER_COLLIDABLE_OFFSETS.0, PLAYER_COLLIDABLE_OFFSETS.1
         ),
         Rotation(0.0),
-        Score { value: 0.0 }
+        Score::default(),
+        RaceClock::default(),
     ))
     .with_children(|parent| {
         parent.spawn((
@@ -436,7 +611,7 @@ pub fn setup(
 
     commands.spawn((
         TextBundle::from_section(
-            "Time: 0.0",
+            format_result(&RaceClock::default(), &Score::default()),
             text_style,
         )
         .with_text_alignment(text_alignment)
@@ -1,20 +1,26 @@
 use bevy::{prelude::*, math::vec2};
-use rand::{self, Rng};
+use rand::{Rng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     Alive,
     GameState,
+    RaceSchedule,
     SCALE_FACTOR,
     GameResources,
     player::{
         self,
         Player,
         Score,
+        RaceClock,
+        Disqualified,
         PLAYER_Z_INDEX
     },
     cleanup,
     despawn,
-    SPRITE_SIZE, sounds::PostHitEvent, collidable::{Collidable}
+    obstacle,
+    replay::ReplayPlayback,
+    SPRITE_SIZE, sounds::PostHitEvent, collidable::{Collidable}, simulation::{SimulationSet, Interpolated}
 };
 
 pub const GAP_RANGE_X : (f32, f32) = (SPRITE_SIZE * SCALE_FACTOR * 2.5, SPRITE_SIZE * SCALE_FACTOR * 6.0);
@@ -22,29 +28,31 @@ pub const GAP_RANGE_Y: (f32, f32) = (SPRITE_SIZE * SCALE_FACTOR * 4.0, SPRITE_SI
 pub const POST_DISTANCE: f32 = SPRITE_SIZE * SCALE_FACTOR * 3.5;
 const FIRST_POST_DISTANCE: f32 = 7.0 * SPRITE_SIZE * SCALE_FACTOR;
 pub const HIT_DETECTION_OFFSET: f32 = 10.0;
+pub const MISSED_GATE_PENALTY: f32 = 5.0;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PostColor {
     Blue,
     Red,
 }
 
 #[derive(Resource)]
-pub struct PostsSpawner {
+pub struct PostsSpawner<R: Rng> {
     x: f32,
     pub y: f32,
-    color: PostColor
+    color: PostColor,
+    rng: R,
 }
 
-impl PostsSpawner {
-    pub fn new() -> Self {
+impl<R: Rng> PostsSpawner<R> {
+    pub fn new(rng: R) -> Self {
         Self {
-            x: 0.0, y: 0.0, color: PostColor::Blue
+            x: 0.0, y: 0.0, color: PostColor::Blue, rng
         }
     }
 }
 
-impl Iterator for PostsSpawner {
+impl<R: Rng> Iterator for PostsSpawner<R> {
     type Item = (PostColor, f32, f32);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -52,14 +60,13 @@ impl Iterator for PostsSpawner {
             self.y = -FIRST_POST_DISTANCE;
             return Some((self.color.clone(), self.x, self.y))
         }
-        let mut rng = rand::thread_rng();
         let (color, signum) = match self.color {
             PostColor::Blue => (PostColor::Red, 1.0),
             PostColor::Red => (PostColor::Blue, -1.0)
         };
 
-        self.x = self.x + rng.gen_range(GAP_RANGE_X.0..GAP_RANGE_X.1) * signum;
-        self.y = self.y - rng.gen_range(GAP_RANGE_Y.0..GAP_RANGE_Y.1);
+        self.x = self.x + self.rng.gen_range(GAP_RANGE_X.0..GAP_RANGE_X.1) * signum;
+        self.y = self.y - self.rng.gen_range(GAP_RANGE_Y.0..GAP_RANGE_Y.1);
         self.color = color;
 
         Some((self.color.clone(), self.x, self.y))
@@ -70,6 +77,27 @@ impl Iterator for PostsSpawner {
 #[derive(Component)]
 pub struct Posts;
 
+/// What happens when the skier goes around a gate instead of through it.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq)]
+pub enum GateRules {
+    /// Every missed gate adds a time penalty.
+    #[default]
+    Penalty,
+    /// The first missed gate disqualifies.
+    Strict,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct GateResults {
+    pub passed: usize,
+    pub missed: usize,
+    /// Race time when each gate was crossed, in course order.
+    pub splits: Vec<f32>,
+}
+
+#[derive(Component)]
+struct MissedGatesText;
+
 pub struct PostsPlugin;
 
 impl Plugin for PostsPlugin {
@@ -78,21 +106,60 @@ impl Plugin for PostsPlugin {
             // .insert_resource(PostsSpawner {
             //     x: 0.0, y: 0.0, color: PostColor::Blue
             // })
-            .add_system(setup.after(player::setup).in_schedule(OnEnter(GameState::Playing)))
-            .add_system(despawn::<Posts>.in_schedule(OnExit(GameState::GameOver)))
+            .init_resource::<GateRules>()
+            .init_resource::<GateResults>()
+            .add_system(setup.after(player::setup).in_schedule(RaceSchedule::Setup))
+            .add_systems(
+                (
+                    despawn::<Posts>,
+                    despawn::<MissedGatesText>,
+                ).in_schedule(RaceSchedule::Teardown)
+            )
             .add_systems(
                 (
                     spawn_posts,
-                    detect_posts_hit,
                     cleanup::<Posts>,
+                    update_missed_gates_text,
                 ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_system(
+                detect_posts_hit
+                    .after(obstacle::update_collidables)
+                    .in_set(SimulationSet::Tick)
+                    .in_schedule(CoreSchedule::FixedUpdate)
             );
     }
 }
 
 fn setup(
-    spawner_r: Option<ResMut<PostsSpawner>>,
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    mut gate_results: ResMut<GateResults>,
+    spawner_r: Option<ResMut<PostsSpawner<StdRng>>>,
 ) {
+    *gate_results = GateResults::default();
+    commands.spawn((
+        TextBundle::from_section(
+            "Missed: 0",
+            TextStyle {
+                font: game_resources.font_handle.clone(),
+                font_size: 24.0,
+                color: Color::BLACK,
+            },
+        )
+        .with_text_alignment(TextAlignment::Right)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(1.),
+                top: Val::Px(25.),
+                ..default()
+            },
+            ..default()
+        }),
+        MissedGatesText,
+    ));
+
     let Some(mut spawner_r) = spawner_r else {
         return;
     };
@@ -106,7 +173,7 @@ fn spawn_posts(
     window: Query<&Window>,
     camera_q: Query<&Transform, With<Camera>>,
     game_resources: Res<GameResources>,
-    spawner_r: Option<ResMut<PostsSpawner>>,
+    spawner_r: Option<ResMut<PostsSpawner<StdRng>>>,
 ) {
     let Some(mut spawner_r) = spawner_r else {
         return;
@@ -124,6 +191,16 @@ fn spawn_posts(
         return;
     };
 
+    spawn_gate(&mut commands, &game_resources, &color, x, y);
+}
+
+pub fn spawn_gate(
+    commands: &mut Commands,
+    game_resources: &GameResources,
+    color: &PostColor,
+    x: f32,
+    y: f32,
+) -> Entity {
     commands.spawn((
         SpatialBundle {
             transform: Transform::from_xyz(x, y, PLAYER_Z_INDEX + 1.1),
@@ -163,36 +240,72 @@ fn spawn_posts(
                 ..default()
             },
         ));
-    });
+    })
+    .id()
 }
 
-
+/// Judges every gate once the skier crosses its line, through the gap between the posts or around them.
 pub fn detect_posts_hit(
     mut commands: Commands,
-    game_resources: Res<GameResources>,
+    gate_rules: Res<GateRules>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut gate_results: ResMut<GateResults>,
     mut ev_posthit: EventWriter<PostHitEvent>,
-    mut player_q: Query<(&Transform, &Collidable, &mut Score), (With<Player>, With<Alive>, Without<Posts>)>,
-    posts_q: Query<(Entity, &Collidable), (With<Posts>, Without<Player>)>
+    mut player_q: Query<(Entity, &Transform, &Interpolated, &Collidable, &mut Score, &mut RaceClock), (With<Player>, With<Alive>, Without<Posts>)>,
+    posts_q: Query<(Entity, &Transform, &Collidable), (With<Posts>, Without<Player>)>
 ) {
     let Ok((
+        player,
         transform_player,
+        interpolated,
         collidable_player,
-        mut score
+        mut score,
+        mut clock
     )) = player_q.get_single_mut() else {
         return;
     };
 
-    for (entity, collidable) in posts_q.iter() {
-        if collidable.top < transform_player.translation.y - game_resources.sprite_size {
+    // a replay is judged by the rules it was recorded with
+    let gate_rules = playback.map_or(*gate_rules, |playback| playback.gate_rules());
+    let start = interpolated.previous();
+    let end = transform_player.translation.truncate();
+    for (entity, transform, collidable) in posts_q.iter() {
+        let gate = transform.translation.truncate();
+        if end.y > gate.y || start.y <= gate.y {
             continue;
         }
-
         if collidable.intersect(collidable_player) {
             ev_posthit.send(PostHitEvent);
-            commands.entity(entity).remove::<Collidable>();
-        } else if collidable.bottom - game_resources.sprite_size > transform_player.translation.y {
+        }
+
+        gate_results.splits.push(clock.time());
+        let crossing_x = start.x + (end.x - start.x) * (start.y - gate.y) / (start.y - end.y);
+        if (crossing_x - gate.x).abs() < POST_DISTANCE / 2.0 {
+            gate_results.passed += 1;
             score.increase();
-            commands.entity(entity).remove::<Collidable>();
+        } else {
+            gate_results.missed += 1;
+            score.decrease();
+            match gate_rules {
+                GateRules::Penalty => clock.add_penalty(MISSED_GATE_PENALTY),
+                GateRules::Strict => {
+                    commands.entity(player).insert(Disqualified);
+                },
+            }
         }
+        commands.entity(entity).remove::<Collidable>();
+    }
+}
+
+fn update_missed_gates_text(
+    gate_results: Res<GateResults>,
+    mut text_q: Query<&mut Text, With<MissedGatesText>>,
+) {
+    if !gate_results.is_changed() {
+        return;
     }
+    let Ok(mut text) = text_q.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = format!("Missed: {}", gate_results.missed);
 }
@@ -0,0 +1,316 @@
+use bevy::{prelude::*, ecs::system::SystemParam};
+
+use crate::{
+    GameState,
+    RaceSchedule,
+    player::{Player, SkiProfile, self},
+    uicontrols::{UiControlType, SkiStance, self},
+    course::{CourseId, select_course},
+    yeti::{Difficulty, YetiSettings},
+    posts::GateRules,
+    simulation::SimulationSet,
+    storage::{GameStorage, Storage},
+};
+
+const REPLAY_MAGIC: &[u8; 4] = b"PRRP";
+const REPLAY_VERSION: u8 = 1;
+const REPLAY_KEY: &str = "replay";
+
+#[derive(Clone)]
+pub struct ReplayFrame {
+    pub control_type: Option<UiControlType>,
+    /// Stored in 1/255 steps, `player_input` quantizes analog steering the same way.
+    pub steering: f32,
+    pub stance: SkiStance,
+    pub delta: f32,
+}
+
+/// Recorded run, every frame stores the input of one simulation tick and the tick length.
+#[derive(Clone)]
+pub struct Replay {
+    pub course: CourseId,
+    pub ski_profile: SkiProfile,
+    pub yeti_settings: YetiSettings,
+    pub gate_rules: GateRules,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(REPLAY_MAGIC.len() + 16 + self.frames.len() * 6);
+        bytes.extend_from_slice(REPLAY_MAGIC);
+        bytes.push(REPLAY_VERSION);
+        match &self.course {
+            CourseId::Seed(seed) => {
+                bytes.push(0);
+                bytes.extend_from_slice(&seed.to_le_bytes());
+            },
+            CourseId::Course(path) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&(path.len() as u16).to_le_bytes());
+                bytes.extend_from_slice(path.as_bytes());
+            }
+        }
+        bytes.push(match self.ski_profile {
+            SkiProfile::Physics => 0,
+            SkiProfile::Arcade => 1,
+        });
+        bytes.push(match self.yeti_settings.difficulty {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+            Difficulty::Nightmare => 3,
+        });
+        bytes.push(self.yeti_settings.rubber_banding as u8);
+        bytes.push(match self.gate_rules {
+            GateRules::Penalty => 0,
+            GateRules::Strict => 1,
+        });
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for frame in self.frames.iter() {
+            bytes.extend_from_slice(&frame.delta.to_le_bytes());
+            let control_type = match frame.control_type {
+                None => 0,
+                Some(UiControlType::Left) => 1,
+                Some(UiControlType::Right) => 2,
+            };
+            let stance = match frame.stance {
+                SkiStance::Normal => 0,
+                SkiStance::Tuck => 1,
+                SkiStance::Brake => 2,
+            };
+            bytes.push(control_type | stance << 2);
+            bytes.push((frame.steering.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader { bytes, position: 0 };
+        if reader.take(REPLAY_MAGIC.len())? != REPLAY_MAGIC || reader.take(1)?[0] != REPLAY_VERSION {
+            return None;
+        }
+        let course = match reader.take(1)?[0] {
+            0 => CourseId::Seed(u64::from_le_bytes(reader.take(8)?.try_into().ok()?)),
+            1 => {
+                let length = u16::from_le_bytes(reader.take(2)?.try_into().ok()?) as usize;
+                CourseId::Course(String::from_utf8(reader.take(length)?.to_vec()).ok()?)
+            },
+            _ => return None,
+        };
+        let ski_profile = match reader.take(1)?[0] {
+            0 => SkiProfile::Physics,
+            1 => SkiProfile::Arcade,
+            _ => return None,
+        };
+        let difficulty = match reader.take(1)?[0] {
+            0 => Difficulty::Easy,
+            1 => Difficulty::Normal,
+            2 => Difficulty::Hard,
+            3 => Difficulty::Nightmare,
+            _ => return None,
+        };
+        let rubber_banding = match reader.take(1)?[0] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        let yeti_settings = YetiSettings { difficulty, rubber_banding };
+        let gate_rules = match reader.take(1)?[0] {
+            0 => GateRules::Penalty,
+            1 => GateRules::Strict,
+            _ => return None,
+        };
+        let frames_count = u32::from_le_bytes(reader.take(4)?.try_into().ok()?) as usize;
+        let mut frames = Vec::with_capacity(frames_count);
+        for _ in 0..frames_count {
+            let delta = f32::from_le_bytes(reader.take(4)?.try_into().ok()?);
+            let input = reader.take(1)?[0];
+            let control_type = match input & 0b11 {
+                0 => None,
+                1 => Some(UiControlType::Left),
+                2 => Some(UiControlType::Right),
+                _ => return None,
+            };
+            let stance = match input >> 2 {
+                0 => SkiStance::Normal,
+                1 => SkiStance::Tuck,
+                2 => SkiStance::Brake,
+                _ => return None,
+            };
+            let steering = reader.take(1)?[0] as f32 / 255.0;
+            frames.push(ReplayFrame { control_type, steering, stance, delta });
+        }
+        Some(Self { course, ski_profile, yeti_settings, gate_rules, frames })
+    }
+
+    pub fn load(storage: &dyn Storage) -> Option<Self> {
+        let bytes = storage.load_bytes(REPLAY_KEY)?;
+        let replay = Self::from_bytes(&bytes);
+        if replay.is_none() {
+            warn!("Stored replay is not valid");
+        }
+        replay
+    }
+
+    pub fn save(&self, storage: &dyn Storage) {
+        if let Err(err) = storage.save_bytes(REPLAY_KEY, &self.to_bytes()) {
+            error!("Unable to save {}: {}", REPLAY_KEY, err);
+        }
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.position..self.position + count)?;
+        self.position += count;
+        Some(slice)
+    }
+}
+
+#[derive(Resource, Default)]
+struct ReplayRecorder {
+    frames: Vec<ReplayFrame>,
+}
+
+#[derive(Resource)]
+pub struct LastReplay(pub Replay);
+
+/// Present while a replay is being played back, `player_input` reads from it instead of the controls.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    index: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, index: 0 }
+    }
+
+    pub fn next_frame(&mut self) -> Option<&ReplayFrame> {
+        let frame = self.replay.frames.get(self.index);
+        self.index += 1;
+        frame
+    }
+
+    pub fn ski_profile(&self) -> SkiProfile {
+        self.replay.ski_profile
+    }
+
+    pub fn yeti_settings(&self) -> YetiSettings {
+        self.replay.yeti_settings
+    }
+
+    pub fn gate_rules(&self) -> GateRules {
+        self.replay.gate_rules
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameStorage>()
+            .init_resource::<ReplayRecorder>()
+            .add_startup_system(load_last_replay)
+            .add_systems((reset_recorder, rewind_playback).in_schedule(RaceSchedule::Setup))
+            .add_system(
+                record_input
+                    .after(uicontrols::player_input)
+                    .before(player::update_player)
+                    .in_set(SimulationSet::Tick)
+                    .in_schedule(CoreSchedule::FixedUpdate)
+            )
+            .add_system(finish_run.in_schedule(OnEnter(GameState::GameOver)));
+    }
+}
+
+/// Starts playing `replay` back, the state is switched to `GameState::Playing`.
+pub fn start_replay(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    app_state: &mut NextState<GameState>,
+    replay: &Replay,
+) {
+    select_course(commands, asset_server, &replay.course);
+    commands.insert_resource(ReplayPlayback::new(replay.clone()));
+    app_state.set(GameState::Playing);
+}
+
+fn rewind_playback(
+    playback: Option<ResMut<ReplayPlayback>>,
+) {
+    if let Some(mut playback) = playback {
+        playback.index = 0;
+    }
+}
+
+fn reset_recorder(
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    recorder.frames.clear();
+}
+
+fn record_input(
+    fixed_time: Res<FixedTime>,
+    mut recorder: ResMut<ReplayRecorder>,
+    player_q: Query<&Player>,
+) {
+    let player = player_q.get_single().ok();
+    recorder.frames.push(ReplayFrame {
+        control_type: player.and_then(|player| player.control_type.clone()),
+        steering: player.map_or(0.0, |player| player.steering),
+        stance: player.map_or(SkiStance::Normal, |player| player.stance),
+        delta: fixed_time.period.as_secs_f32(),
+    });
+}
+
+/// Settings of the running race that a replay has to repeat.
+#[derive(SystemParam)]
+struct RaceSettings<'w> {
+    ski_profile: Res<'w, SkiProfile>,
+    yeti_settings: Res<'w, YetiSettings>,
+    gate_rules: Res<'w, GateRules>,
+}
+
+fn finish_run(
+    mut commands: Commands,
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    course_id: Option<Res<CourseId>>,
+    race_settings: RaceSettings,
+    storage: Res<GameStorage>,
+) {
+    if playback.is_some() {
+        commands.remove_resource::<ReplayPlayback>();
+        return;
+    }
+    let Some(course_id) = course_id else {
+        return;
+    };
+    let replay = Replay {
+        course: course_id.clone(),
+        ski_profile: *race_settings.ski_profile,
+        yeti_settings: *race_settings.yeti_settings,
+        gate_rules: *race_settings.gate_rules,
+        frames: std::mem::take(&mut recorder.frames),
+    };
+    replay.save(storage.0.as_ref());
+    commands.insert_resource(LastReplay(replay));
+}
+
+fn load_last_replay(
+    mut commands: Commands,
+    storage: Res<GameStorage>,
+) {
+    if let Some(replay) = Replay::load(storage.0.as_ref()) {
+        commands.insert_resource(LastReplay(replay));
+    }
+}
@@ -0,0 +1,475 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    GameResources,
+    despawn,
+    gamepad::ButtonPresses,
+    mixer::{AudioMixer, Channel},
+    NORMAL_BUTTON,
+    SELECTED_BUTTON,
+    storage::{GameStorage, Storage, load_ron, save_ron},
+    uicontrols::{ControlScheme, ControlSchemeType},
+    yeti::{Difficulty, YetiSettings},
+};
+
+const SETTINGS_KEY: &str = "settings";
+const VOLUME_STEP: f32 = 0.1;
+const SLIDER_STEP: f32 = 0.05;
+const SLIDER_WIDTH: f32 = 160.0;
+const LARGE_TEXT_SCALE: f32 = 1.25;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CameraMode {
+    /// The camera is glued to the skier.
+    #[default]
+    Locked,
+    /// The camera eases after the skier sideways, down the slope it stays locked.
+    Smooth,
+}
+
+impl CameraMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Locked => "Locked",
+            CameraMode::Smooth => "Smooth",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            CameraMode::Locked => CameraMode::Smooth,
+            CameraMode::Smooth => CameraMode::Locked,
+        }
+    }
+}
+
+/// Player preferences kept between sessions, missing fields of an older file take their default.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// `None` keeps the scheme guessed from the platform.
+    pub control_scheme: Option<ControlSchemeType>,
+    /// Yeti difficulty the menu starts with.
+    pub difficulty: Difficulty,
+    pub camera: CameraMode,
+    /// Scales the whole interface up.
+    pub large_text: bool,
+    /// Stops purely decorative motion, e.g. the spinning stun stars.
+    pub reduced_motion: bool,
+}
+
+impl Settings {
+    pub fn load(storage: &dyn Storage) -> Self {
+        load_ron(storage, SETTINGS_KEY)
+    }
+
+    pub fn save(&self, storage: &dyn Storage) {
+        save_ron(storage, SETTINGS_KEY, self);
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum SettingsControl {
+    Volume(Channel, f32),
+    Slider(Channel),
+    Mute(Channel),
+    ControlScheme,
+    Difficulty,
+    Camera,
+    LargeText,
+    ReducedMotion,
+    Back,
+}
+
+/// Filled part of a volume slider.
+#[derive(Component)]
+struct SliderFill(Channel);
+
+#[derive(Component)]
+struct SettingsElement;
+
+/// Font sizes a text was spawned with.
+#[derive(Component)]
+struct BaseFontSizes(Vec<f32>);
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameStorage>()
+            .add_startup_system(load_settings)
+            .add_systems(
+                (
+                    apply_settings,
+                    scale_text,
+                    save_settings,
+                )
+            )
+            .add_system(setup_settings.in_schedule(OnEnter(GameState::Settings)))
+            .add_systems(
+                (
+                    controls_interaction,
+                    slider_interaction,
+                    update_settings_screen.after(controls_interaction).after(slider_interaction),
+                ).in_set(OnUpdate(GameState::Settings))
+            )
+            .add_system(despawn::<SettingsElement>.in_schedule(OnExit(GameState::Settings)));
+    }
+}
+
+fn load_settings(
+    mut commands: Commands,
+    storage: Res<GameStorage>,
+    mut yeti_settings: ResMut<YetiSettings>,
+) {
+    let settings = Settings::load(storage.0.as_ref());
+    yeti_settings.difficulty = settings.difficulty;
+    commands.insert_resource(settings);
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut control_scheme: ResMut<ControlScheme>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if let Some(kind) = settings.control_scheme {
+        control_scheme.kind = kind;
+    }
+}
+
+/// Large text only grows the fonts, the layouts are in pixels and would be pushed off screen by `UiScale`.
+fn scale_text(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut text_q: Query<(Entity, &mut Text, Option<&BaseFontSizes>)>,
+) {
+    let scale = if settings.large_text { LARGE_TEXT_SCALE } else { 1.0 };
+    for (entity, mut text, base_font_sizes) in text_q.iter_mut() {
+        let base_font_sizes = match base_font_sizes {
+            Some(base_font_sizes) if settings.is_changed() => base_font_sizes.0.clone(),
+            Some(_) => continue,
+            None => {
+                let base_font_sizes: Vec<f32> = text.sections.iter().map(|section| section.style.font_size).collect();
+                commands.entity(entity).insert(BaseFontSizes(base_font_sizes.clone()));
+                base_font_sizes
+            },
+        };
+        for (section, base_font_size) in text.sections.iter_mut().zip(base_font_sizes) {
+            section.style.font_size = base_font_size * scale;
+        }
+    }
+}
+
+fn save_settings(
+    settings: Res<Settings>,
+    storage: Res<GameStorage>,
+) {
+    if settings.is_changed() && !settings.is_added() {
+        settings.save(storage.0.as_ref());
+    }
+}
+
+fn controls_interaction(
+    mut presses: ButtonPresses<SettingsControl>,
+    control_scheme: Res<ControlScheme>,
+    mut settings: ResMut<Settings>,
+    mut mixer: ResMut<AudioMixer>,
+    mut yeti_settings: ResMut<YetiSettings>,
+    mut app_state: ResMut<NextState<GameState>>,
+) {
+    for control in presses.iter() {
+        match *control {
+            SettingsControl::Volume(channel, delta) => {
+                let level = mixer.channel_mut(channel);
+                level.volume = step_volume(level.volume + delta, VOLUME_STEP);
+            },
+            SettingsControl::Mute(channel) => {
+                mixer.toggle_mute(channel);
+            },
+            SettingsControl::ControlScheme => {
+                settings.control_scheme = Some(match get_control_scheme(&settings, &control_scheme) {
+                    ControlSchemeType::Mobile => ControlSchemeType::Desktop,
+                    ControlSchemeType::Desktop => ControlSchemeType::Mobile,
+                });
+            },
+            SettingsControl::Difficulty => {
+                settings.difficulty = settings.difficulty.next();
+                yeti_settings.difficulty = settings.difficulty;
+            },
+            SettingsControl::Camera => {
+                settings.camera = settings.camera.next();
+            },
+            SettingsControl::LargeText => {
+                settings.large_text = !settings.large_text;
+            },
+            SettingsControl::ReducedMotion => {
+                settings.reduced_motion = !settings.reduced_motion;
+            },
+            SettingsControl::Back => {
+                app_state.set(GameState::MainMenu);
+            },
+            SettingsControl::Slider(_) => {},
+        }
+    }
+}
+
+/// Sliders follow the cursor for as long as they are held.
+fn slider_interaction(
+    slider_q: Query<(&Interaction, &RelativeCursorPosition, &SettingsControl)>,
+    mut mixer: ResMut<AudioMixer>,
+) {
+    for (interaction, cursor, control) in &slider_q {
+        let SettingsControl::Slider(channel) = *control else {
+            continue;
+        };
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let Some(normalized) = cursor.normalized else {
+            continue;
+        };
+        let volume = step_volume(normalized.x, SLIDER_STEP);
+        if mixer.channel(channel).volume != volume {
+            mixer.channel_mut(channel).volume = volume;
+        }
+    }
+}
+
+fn step_volume(volume: f32, step: f32) -> f32 {
+    ((volume / step).round() * step).clamp(0.0, 1.0)
+}
+
+fn get_control_scheme(settings: &Settings, control_scheme: &ControlScheme) -> ControlSchemeType {
+    settings.control_scheme.unwrap_or(control_scheme.kind)
+}
+
+fn get_label(control: SettingsControl, settings: &Settings, mixer: &AudioMixer, control_scheme: &ControlScheme) -> Option<&'static str> {
+    match control {
+        SettingsControl::ControlScheme => Some(match get_control_scheme(settings, control_scheme) {
+            ControlSchemeType::Mobile => "Mobile",
+            ControlSchemeType::Desktop => "Desktop",
+        }),
+        SettingsControl::Difficulty => Some(settings.difficulty.name()),
+        SettingsControl::Camera => Some(settings.camera.name()),
+        SettingsControl::Mute(_) => Some("Mute"),
+        _ => get_toggle(control, settings, mixer).map(|toggle| if toggle { "On" } else { "Off" }),
+    }
+}
+
+fn get_toggle(control: SettingsControl, settings: &Settings, mixer: &AudioMixer) -> Option<bool> {
+    match control {
+        SettingsControl::Mute(channel) => Some(mixer.channel(channel).muted),
+        SettingsControl::LargeText => Some(settings.large_text),
+        SettingsControl::ReducedMotion => Some(settings.reduced_motion),
+        _ => None,
+    }
+}
+
+fn get_button_color(toggle: bool) -> BackgroundColor {
+    if toggle { SELECTED_BUTTON.into() } else { NORMAL_BUTTON.into() }
+}
+
+fn update_settings_screen(
+    settings: Res<Settings>,
+    mixer: Res<AudioMixer>,
+    control_scheme: Res<ControlScheme>,
+    mut button_q: Query<(&SettingsControl, &mut BackgroundColor, &Children), Without<SliderFill>>,
+    mut text_q: Query<&mut Text>,
+    mut fill_q: Query<(&SliderFill, &mut Style)>,
+) {
+    if !settings.is_changed() && !mixer.is_changed() {
+        return;
+    }
+    for (control, mut color, children) in button_q.iter_mut() {
+        if let Some(toggle) = get_toggle(*control, &settings, &mixer) {
+            *color = get_button_color(toggle);
+        }
+        let Some(label) = get_label(*control, &settings, &mixer, &control_scheme) else {
+            continue;
+        };
+        for &child in children.iter() {
+            if let Ok(mut text) = text_q.get_mut(child) {
+                text.sections[0].value = label.to_string();
+            }
+        }
+    }
+    for (fill, mut style) in fill_q.iter_mut() {
+        style.size.width = Val::Percent(mixer.channel(fill.0).volume * 100.0);
+    }
+}
+
+fn setup_settings(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    settings: Res<Settings>,
+    mixer: Res<AudioMixer>,
+    control_scheme: Res<ControlScheme>,
+) {
+    let text_style = TextStyle {
+        font: game_resources.font_handle.clone(),
+        font_size: 16.0,
+        color: Color::BLACK,
+    };
+    let button_style = Style {
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        padding: UiRect {
+            left: Val::Px(12.0),
+            right: Val::Px(12.0),
+            top: Val::Px(4.0),
+            bottom: Val::Px(4.0)
+        },
+        ..default()
+    };
+    let spawn_button = |builder: &mut ChildBuilder, control: SettingsControl, label: &str, color: BackgroundColor| {
+        builder.spawn((
+            ButtonBundle {
+                style: button_style.clone(),
+                background_color: color,
+                ..default()
+            },
+            control,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+    };
+    let spawn_row = |builder: &mut ChildBuilder, name: &str, spawn_controls: &dyn Fn(&mut ChildBuilder)| {
+        builder.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                gap: Size::width(Val::Px(8.0)),
+                ..default()
+            },
+            ..default()
+        }).with_children(|builder| {
+            builder.spawn(
+                TextBundle::from_section(name, text_style.clone())
+                    .with_style(Style {
+                        margin: UiRect {
+                            right: Val::Px(16.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+            );
+            spawn_controls(builder);
+        });
+    };
+    let spawn_toggle = |builder: &mut ChildBuilder, control: SettingsControl| {
+        let toggle = get_toggle(control, &settings, &mixer).unwrap_or_default();
+        let label = get_label(control, &settings, &mixer, &control_scheme).unwrap_or_default();
+        spawn_button(builder, control, label, get_button_color(toggle));
+    };
+    let spawn_slider = |builder: &mut ChildBuilder, channel: Channel| {
+        spawn_button(builder, SettingsControl::Volume(channel, -VOLUME_STEP), "-", NORMAL_BUTTON.into());
+        builder.spawn((
+            ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(SLIDER_WIDTH), Val::Px(16.0)),
+                    ..default()
+                },
+                background_color: NORMAL_BUTTON.into(),
+                ..default()
+            },
+            RelativeCursorPosition::default(),
+            SettingsControl::Slider(channel),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(mixer.channel(channel).volume * 100.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    background_color: SELECTED_BUTTON.into(),
+                    ..default()
+                },
+                SliderFill(channel),
+            ));
+        });
+        spawn_button(builder, SettingsControl::Volume(channel, VOLUME_STEP), "+", NORMAL_BUTTON.into());
+        spawn_toggle(builder, SettingsControl::Mute(channel));
+    };
+    let spawn_cycle = |builder: &mut ChildBuilder, control: SettingsControl| {
+        let label = get_label(control, &settings, &mixer, &control_scheme).unwrap_or_default();
+        spawn_button(builder, control, label, NORMAL_BUTTON.into());
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(40.0),
+                        ..default()
+                    },
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        ..default()
+                    },
+                    gap: Size::height(Val::Px(8.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsElement,
+        ))
+        .with_children(|builder| {
+            for channel in Channel::ALL {
+                spawn_row(builder, channel.name(), &|builder| spawn_slider(builder, channel));
+            }
+            spawn_row(builder, "Controls", &|builder| spawn_cycle(builder, SettingsControl::ControlScheme));
+            spawn_row(builder, "Yeti", &|builder| spawn_cycle(builder, SettingsControl::Difficulty));
+            spawn_row(builder, "Camera", &|builder| spawn_cycle(builder, SettingsControl::Camera));
+            spawn_row(builder, "Large text", &|builder| spawn_toggle(builder, SettingsControl::LargeText));
+            spawn_row(builder, "Reduced motion", &|builder| spawn_toggle(builder, SettingsControl::ReducedMotion));
+        });
+
+    commands.spawn((
+        ButtonBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(400.0),
+                    ..default()
+                },
+                margin: UiRect {
+                    left: Val::Auto,
+                    right: Val::Auto,
+                    ..default()
+                },
+                padding: UiRect {
+                    left: Val::Px(12.0),
+                    right: Val::Px(12.0),
+                    top: Val::Px(8.0),
+                    bottom: Val::Px(8.0)
+                },
+                ..button_style
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        },
+        SettingsElement,
+        SettingsControl::Back,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "Back",
+            TextStyle {
+                font_size: 24.0,
+                ..text_style
+            },
+        ));
+    });
+}
@@ -0,0 +1,95 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::GameState;
+
+pub const TICKS_PER_SECOND: f32 = 60.0;
+
+/// Gameplay runs in `CoreSchedule::FixedUpdate`, every system that moves things or ticks
+/// gameplay timers belongs to `SimulationSet::Tick` and reads `FixedTime::period` instead of `Time::delta`.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    Begin,
+    Tick,
+    End,
+}
+
+/// Rendered position is interpolated between the last two simulated positions.
+#[derive(Component)]
+pub struct Interpolated {
+    previous: Vec2,
+    current: Vec2,
+}
+
+impl Interpolated {
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            previous: position,
+            current: position,
+        }
+    }
+
+    /// Position at the start of the running tick.
+    pub fn previous(&self) -> Vec2 {
+        self.previous
+    }
+
+    /// Last simulated position, during a tick it is still the one the tick started from.
+    pub fn current(&self) -> Vec2 {
+        self.current
+    }
+}
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(FixedTime::new_from_secs(1.0 / TICKS_PER_SECOND))
+            .edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+                schedule.configure_sets(
+                    (
+                        SimulationSet::Begin,
+                        SimulationSet::Tick.run_if(in_state(GameState::Playing)),
+                        SimulationSet::End,
+                    ).chain()
+                );
+            })
+            .add_system(begin_tick.in_set(SimulationSet::Begin).in_schedule(CoreSchedule::FixedUpdate))
+            .add_system(end_tick.in_set(SimulationSet::End).in_schedule(CoreSchedule::FixedUpdate))
+            .add_system(
+                interpolate
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(TransformSystem::TransformPropagate)
+            );
+    }
+}
+
+fn begin_tick(
+    mut interpolated_q: Query<(&mut Transform, &mut Interpolated)>,
+) {
+    for (mut transform, mut interpolated) in interpolated_q.iter_mut() {
+        transform.translation.x = interpolated.current.x;
+        transform.translation.y = interpolated.current.y;
+        interpolated.previous = interpolated.current;
+    }
+}
+
+fn end_tick(
+    mut interpolated_q: Query<(&Transform, &mut Interpolated)>,
+) {
+    for (transform, mut interpolated) in interpolated_q.iter_mut() {
+        interpolated.current = transform.translation.truncate();
+    }
+}
+
+pub fn interpolate(
+    fixed_time: Res<FixedTime>,
+    mut interpolated_q: Query<(&mut Transform, &Interpolated)>,
+) {
+    let alpha = (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).min(1.0);
+    for (mut transform, interpolated) in interpolated_q.iter_mut() {
+        let position = interpolated.previous.lerp(interpolated.current, alpha);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
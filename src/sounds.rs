@@ -1,13 +1,12 @@
 use bevy::{prelude::*};
 
-use crate::GameState;
+use crate::{GameState, mixer::MixedAudio};
 
 #[derive(Resource)]
 struct SoundsResource {
     post_hit: Handle<AudioSource>,
     // scrape_1: Handle<AudioSource>,
     // scrape_2: Handle<AudioSource>,
-    sound_on: bool,
 }
 
 pub struct PostHitEvent;
@@ -20,8 +19,7 @@ impl Plugin for SoundPlugin {
             .add_startup_system(setup)
             .add_systems((
                 on_post_hit,
-            ).in_set(OnUpdate(GameState::Playing)))
-            .add_system(sound_control);
+            ).in_set(OnUpdate(GameState::Playing)));
     }
 }
 
@@ -33,27 +31,15 @@ fn setup(
         post_hit: asset_server.load("posthit.ogg"),
         // scrape_1: asset_server.load("scrape-1.ogg"),
         // scrape_2: asset_server.load("scrape-2.ogg"),
-        sound_on: true,
     });
 }
 
 fn on_post_hit(
-    audio: Res<Audio>,
+    audio: MixedAudio,
     sound_resource: Res<SoundsResource>,
     mut ev_posthit: EventReader<PostHitEvent>,
 ) {
     for _ in ev_posthit.iter() {
-        if sound_resource.sound_on {
-            audio.play(sound_resource.post_hit.clone());
-        }
+        audio.play_sfx(sound_resource.post_hit.clone());
     }
 }
-
-fn sound_control(
-    mut sound_resource: ResMut<SoundsResource>,
-    keyboard_input: Res<Input<KeyCode>>,
-) {
-    if keyboard_input.just_pressed(KeyCode::M) {
-        sound_resource.sound_on = !sound_resource.sound_on;
-    }
-}
\ No newline at end of file
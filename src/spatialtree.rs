@@ -1,50 +1,73 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use bevy::{prelude::*};
 
+use crate::{collidable::Collidable, obstacle::Obstacle};
+
 pub const SPATIAL_TREE_SEARCH_RADIUS: f32 = 100.0;
 
+type TileRange = (isize, isize, isize, isize);
+type MovedObstacles = (With<Obstacle>, Changed<Collidable>);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub entity: Entity,
+    pub point: Vec2,
+}
+
+/// Uniform grid over the obstacles, every entity is stored in all tiles its bounds overlap.
 #[derive(Resource)]
 pub struct SpatialTree {
     tile_size: f32,
-    grid: HashMap<(isize, isize), Vec<Entity>>
+    grid: HashMap<(isize, isize), Vec<Entity>>,
+    entities: HashMap<Entity, TileRange>,
 }
 
 impl SpatialTree {
     pub fn new(tile_size: f32) -> Self {
         Self {
             grid: HashMap::new(),
+            entities: HashMap::new(),
             tile_size
         }
     }
 
     fn loc_to_tile(&self, loc: f32) -> isize {
-        (loc / self.tile_size) as isize
+        (loc / self.tile_size).floor() as isize
     }
 
-    fn get_affected_tiles(&self, x: f32, y: f32, size: f32) -> (isize, isize, isize, isize) {
+    fn get_affected_tiles(&self, bounds: Rect) -> TileRange {
         (
-            self.loc_to_tile(x),
-            self.loc_to_tile(y - size),
-            self.loc_to_tile(x + size),
-            self.loc_to_tile(y),
+            self.loc_to_tile(bounds.min.x),
+            self.loc_to_tile(bounds.min.y),
+            self.loc_to_tile(bounds.max.x),
+            self.loc_to_tile(bounds.max.y),
         )
     }
 
-    pub fn insert(&mut self, x: f32, y: f32, size: f32, e: Entity) {
-        let (gx_min, gy_min, gx_max, gy_max) = self.get_affected_tiles(x, y, size);
+    /// Inserts `e` or moves it if it is already stored.
+    pub fn insert(&mut self, e: Entity, bounds: Rect) {
+        let tiles = self.get_affected_tiles(bounds);
+        if self.entities.get(&e) == Some(&tiles) {
+            return;
+        }
+        self.remove(e);
+        let (gx_min, gy_min, gx_max, gy_max) = tiles;
         for gx in gx_min..=gx_max {
             for gy in gy_min..=gy_max {
                 self.grid
                     .entry((gx, gy))
-                    .and_modify(|entries| entries.push(e))
-                    .or_insert(vec![e]);
+                    .or_default()
+                    .push(e);
             }
         }
+        self.entities.insert(e, tiles);
     }
 
-    pub fn remove(&mut self, x: f32, y: f32, size: f32, e: Entity) {
-        let (gx_min, gy_min, gx_max, gy_max) = self.get_affected_tiles(x, y, size);
+    pub fn remove(&mut self, e: Entity) {
+        let Some((gx_min, gy_min, gx_max, gy_max)) = self.entities.remove(&e) else {
+            return;
+        };
         for gx in gx_min..=gx_max {
             for gy in gy_min..=gy_max {
                 let Some(entities) = self.grid.get_mut(&(gx, gy)) else {
@@ -53,17 +76,16 @@ impl SpatialTree {
                 };
 
                 entities.retain(|&entity| entity != e);
+                if entities.is_empty() {
+                    self.grid.remove(&(gx, gy));
+                }
             }
         }
     }
 
-    pub fn reposition(&mut self, from: Vec2, to: Vec2, size: f32, e: Entity) {
-        self.remove(from.x, from.y, size, e);
-        self.insert(to.x, to.y, size, e);
-    }
-
-    pub fn get_at(&self, x: f32, y: f32, size: f32) -> Vec<Entity> {
-        let (gx_min, gy_min, gx_max, gy_max) = self.get_affected_tiles(x, y, size);
+    /// Entities stored in the tiles overlapping `bounds`, candidates for an exact test.
+    pub fn get_at(&self, bounds: Rect) -> Vec<Entity> {
+        let (gx_min, gy_min, gx_max, gy_max) = self.get_affected_tiles(bounds);
         let mut result = vec![];
         for gx in gx_min..=gx_max {
             for gy in gy_min..=gy_max {
@@ -73,13 +95,134 @@ impl SpatialTree {
                 result.extend(entries)
             }
         }
+        result.sort_unstable();
+        result.dedup();
         result
     }
 
-    pub fn cleanup_y(&mut self, y: f32) {
-        let gy = self.loc_to_tile(y);
-        self.grid.retain(|k, _v| {
-            gy <= k.0
-        });
+    /// First entity hit by the segment from `start` to `end`, tiles are visited in the order the segment crosses them.
+    pub fn raycast<'a>(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        collidables: impl Fn(Entity) -> Option<&'a Collidable>,
+    ) -> Option<RayHit> {
+        let direction = end - start;
+        let mut tile = (self.loc_to_tile(start.x), self.loc_to_tile(start.y));
+        let last_tile = (self.loc_to_tile(end.x), self.loc_to_tile(end.y));
+        let (step_x, mut t_max_x, t_delta_x) = self.traversal(start.x, direction.x, tile.0);
+        let (step_y, mut t_max_y, t_delta_y) = self.traversal(start.y, direction.y, tile.1);
+
+        let mut visited = HashSet::new();
+        let mut closest: Option<(f32, RayHit)> = None;
+        loop {
+            for &entity in self.grid.get(&tile).into_iter().flatten() {
+                if !visited.insert(entity) {
+                    continue;
+                }
+                let Some(point) = collidables(entity).and_then(|collidable| collidable.intersect_line(start, end)) else {
+                    continue;
+                };
+                let t = (point - start).dot(direction) / direction.length_squared();
+                let closer = match closest {
+                    Some((closest_t, _)) => t < closest_t,
+                    None => true,
+                };
+                if closer {
+                    closest = Some((t, RayHit { entity, point }));
+                }
+            }
+
+            let t_exit = t_max_x.min(t_max_y);
+            if closest.is_some_and(|(closest_t, _)| closest_t <= t_exit) || tile == last_tile || t_exit > 1.0 {
+                break;
+            }
+            if t_max_x < t_max_y {
+                tile.0 += step_x;
+                t_max_x += t_delta_x;
+            } else {
+                tile.1 += step_y;
+                t_max_y += t_delta_y;
+            }
+        }
+        closest.map(|(_, hit)| hit)
+    }
+
+    /// Step direction, segment parameter of the first tile boundary and parameter distance between boundaries on one axis.
+    fn traversal(&self, start: f32, direction: f32, tile: isize) -> (isize, f32, f32) {
+        if direction > 0.0 {
+            (1, ((tile + 1) as f32 * self.tile_size - start) / direction, self.tile_size / direction)
+        } else if direction < 0.0 {
+            (-1, (tile as f32 * self.tile_size - start) / direction, -self.tile_size / direction)
+        } else {
+            (0, f32::INFINITY, f32::INFINITY)
+        }
+    }
+
+    /// Entities whose collidable overlaps `bounds`.
+    pub fn overlapping<'a>(
+        &self,
+        bounds: Rect,
+        collidables: impl Fn(Entity) -> Option<&'a Collidable>,
+    ) -> Vec<Entity> {
+        self.get_at(bounds).into_iter()
+            .filter(|&entity| collidables(entity).is_some_and(|collidable| collidable.intersect_rect(bounds)))
+            .collect()
+    }
+
+    /// Up to `k` entities closest to `point`, measured to the edge of their collidable and sorted by distance.
+    pub fn nearest<'a>(
+        &self,
+        point: Vec2,
+        k: usize,
+        collidables: impl Fn(Entity) -> Option<&'a Collidable>,
+    ) -> Vec<Entity> {
+        let (cx, cy) = (self.loc_to_tile(point.x), self.loc_to_tile(point.y));
+        let mut seen = HashSet::new();
+        let mut found: Vec<(f32, Entity)> = vec![];
+        let mut ring = 0;
+        while k > 0 && seen.len() < self.entities.len() {
+            for gx in cx - ring..=cx + ring {
+                for gy in cy - ring..=cy + ring {
+                    if (gx - cx).abs() != ring && (gy - cy).abs() != ring {
+                        continue;
+                    }
+                    for &entity in self.grid.get(&(gx, gy)).into_iter().flatten() {
+                        if !seen.insert(entity) {
+                            continue;
+                        }
+                        if let Some(collidable) = collidables(entity) {
+                            let bounds = collidable.bounds();
+                            found.push((point.clamp(bounds.min, bounds.max).distance(point), entity));
+                        }
+                    }
+                }
+            }
+            found.sort_by(|a, b| a.0.total_cmp(&b.0));
+            // everything outside of the visited rings is at least this far away
+            if found.len() >= k && found[k - 1].0 <= ring as f32 * self.tile_size {
+                break;
+            }
+            ring += 1;
+        }
+        found.into_iter().take(k).map(|(_, entity)| entity).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// Keeps the grid in sync with spawned and moved obstacles.
+pub fn index_obstacles(
+    mut spatial_tree: ResMut<SpatialTree>,
+    obstacles_q: Query<(Entity, &Collidable), MovedObstacles>,
+) {
+    for (entity, collidable) in obstacles_q.iter() {
+        spatial_tree.insert(entity, collidable.bounds());
     }
 }
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    RaceSchedule,
+    GameResources,
+    despawn,
+    player::{RaceClock, FinishedPlayer},
+    posts::GateResults,
+    course::CourseId,
+    replay::ReplayPlayback,
+    storage::{GameStorage, Storage, load_ron, save_ron},
+};
+
+const SPLITS_KEY: &str = "splits";
+const SPLIT_DISPLAY_SECONDS: f32 = 2.0;
+const FASTER_COLOR: Color = Color::rgb(0.2, 0.6, 0.2);
+const SLOWER_COLOR: Color = Color::rgb(0.75, 0.2, 0.2);
+
+/// Gate times of the fastest finished run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PersonalBest {
+    pub time: f32,
+    pub splits: Vec<f32>,
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct PersonalBests {
+    runs: HashMap<CourseId, PersonalBest>,
+}
+
+impl PersonalBests {
+    pub fn get(&self, course_id: &CourseId) -> Option<&PersonalBest> {
+        self.runs.get(course_id)
+    }
+
+    /// Keeps `run` when it is faster than the stored one, returns whether it was kept.
+    pub fn update(&mut self, course_id: &CourseId, run: PersonalBest) -> bool {
+        if self.get(course_id).is_some_and(|best| best.time <= run.time) {
+            return false;
+        }
+        self.runs.insert(course_id.clone(), run);
+        true
+    }
+
+    /// Difference to the personal best at the same gate, negative when faster.
+    pub fn delta(&self, course_id: &CourseId, gate: usize, time: f32) -> Option<f32> {
+        self.get(course_id)?.splits.get(gate).map(|best| time - best)
+    }
+
+    pub fn load(storage: &dyn Storage) -> Self {
+        load_ron(storage, SPLITS_KEY)
+    }
+
+    pub fn save(&self, storage: &dyn Storage) {
+        save_ron(storage, SPLITS_KEY, self);
+    }
+}
+
+/// Sent when a finished run replaced the personal best of its course.
+pub struct NewPersonalBest {
+    pub time: f32,
+}
+
+#[derive(Component)]
+struct SplitText(Timer);
+
+#[derive(Component)]
+struct SplitsTable;
+
+pub struct SplitsPlugin;
+
+impl Plugin for SplitsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameStorage>()
+            .add_event::<NewPersonalBest>()
+            .add_startup_system(load_splits)
+            .add_systems(
+                (
+                    show_split,
+                    hide_split,
+                ).in_set(OnUpdate(GameState::Playing))
+            )
+            .add_systems(
+                (
+                    show_splits_table,
+                    store_personal_best.after(show_splits_table),
+                ).in_schedule(OnEnter(GameState::GameOver))
+            )
+            .add_systems(
+                (
+                    despawn::<SplitText>,
+                ).in_schedule(RaceSchedule::Teardown)
+            )
+            .add_systems(
+                (
+                    despawn::<SplitsTable>,
+                ).in_schedule(OnExit(GameState::GameOver))
+            );
+    }
+}
+
+fn load_splits(
+    mut commands: Commands,
+    storage: Res<GameStorage>,
+) {
+    commands.insert_resource(PersonalBests::load(storage.0.as_ref()));
+}
+
+fn format_split(gate: usize, time: f32, delta: Option<f32>) -> String {
+    match delta {
+        Some(delta) => format!("Gate {}: {:.2} / {:+.2}", gate + 1, time, delta),
+        None => format!("Gate {}: {:.2}", gate + 1, time),
+    }
+}
+
+fn get_split_color(delta: Option<f32>) -> Color {
+    match delta {
+        Some(delta) if delta < 0.0 => FASTER_COLOR,
+        Some(delta) if delta > 0.0 => SLOWER_COLOR,
+        _ => Color::BLACK,
+    }
+}
+
+fn show_split(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    gate_results: Res<GateResults>,
+    personal_bests: Res<PersonalBests>,
+    course_id: Option<Res<CourseId>>,
+    text_q: Query<Entity, With<SplitText>>,
+) {
+    if !gate_results.is_changed() {
+        return;
+    }
+    let Some(&time) = gate_results.splits.last() else {
+        return;
+    };
+    let gate = gate_results.splits.len() - 1;
+    let delta = course_id.and_then(|course_id| personal_bests.delta(&course_id, gate, time));
+
+    for entity in text_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            format_split(gate, time, delta),
+            TextStyle {
+                font: game_resources.font_handle.clone(),
+                font_size: 24.0,
+                color: get_split_color(delta),
+            },
+        )
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(50.0),
+                ..default()
+            },
+            margin: UiRect {
+                left: Val::Auto,
+                right: Val::Auto,
+                ..default()
+            },
+            ..default()
+        }),
+        SplitText(Timer::from_seconds(SPLIT_DISPLAY_SECONDS, TimerMode::Once)),
+    ));
+}
+
+fn hide_split(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut text_q: Query<(Entity, &mut SplitText)>,
+) {
+    for (entity, mut split_text) in text_q.iter_mut() {
+        if split_text.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Lists all splits against the previous personal best.
+fn show_splits_table(
+    mut commands: Commands,
+    game_resources: Res<GameResources>,
+    gate_results: Res<GateResults>,
+    personal_bests: Res<PersonalBests>,
+    course_id: Option<Res<CourseId>>,
+) {
+    let Some(course_id) = course_id else {
+        return;
+    };
+    if gate_results.splits.is_empty() {
+        return;
+    }
+    let text_style = TextStyle {
+        font: game_resources.font_handle.clone(),
+        font_size: 16.0,
+        color: Color::BLACK,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    flex_wrap: FlexWrap::Wrap,
+                    align_items: AlignItems::FlexEnd,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(20.0),
+                        right: Val::Px(20.0),
+                        ..default()
+                    },
+                    max_size: Size::height(Val::Percent(90.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            SplitsTable,
+        ))
+        .with_children(|builder| {
+            for (gate, &time) in gate_results.splits.iter().enumerate() {
+                let delta = personal_bests.delta(&course_id, gate, time);
+                let mut style = text_style.clone();
+                style.color = get_split_color(delta);
+                builder.spawn(TextBundle::from_section(format_split(gate, time, delta), style));
+            }
+        });
+}
+
+/// Stores a finished run that beat the personal best of its course, replays never count.
+pub fn store_personal_best(
+    storage: Res<GameStorage>,
+    gate_results: Res<GateResults>,
+    mut personal_bests: ResMut<PersonalBests>,
+    course_id: Option<Res<CourseId>>,
+    playback: Option<Res<ReplayPlayback>>,
+    player_q: Query<&RaceClock, FinishedPlayer>,
+    mut ev_personal_best: EventWriter<NewPersonalBest>,
+) {
+    let (Some(course_id), Ok(clock)) = (course_id, player_q.get_single()) else {
+        return;
+    };
+    if playback.is_some() {
+        return;
+    }
+    let run = PersonalBest {
+        time: clock.time(),
+        splits: gate_results.splits.clone(),
+    };
+    if personal_bests.update(&course_id, run) {
+        personal_bests.save(storage.0.as_ref());
+        ev_personal_best.send(NewPersonalBest { time: clock.time() });
+    }
+}
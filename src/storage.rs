@@ -0,0 +1,141 @@
+#[cfg(feature = "headless")]
+use std::{collections::HashMap, sync::Mutex};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Persists small text blobs under a key, on disk for native builds and in local storage for the browser.
+pub trait Storage: Send + Sync {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&self, key: &str, value: &str) -> Result<(), String>;
+
+    /// Binary blobs are kept as base64 text unless the storage can hold raw bytes.
+    fn load_bytes(&self, key: &str) -> Option<Vec<u8>> {
+        STANDARD.decode(self.load(key)?).ok()
+    }
+
+    fn save_bytes(&self, key: &str, value: &[u8]) -> Result<(), String> {
+        self.save(key, &STANDARD.encode(value))
+    }
+}
+
+/// Reads the RON value under `key`, a missing or unreadable value gives the default.
+pub fn load_ron<T: DeserializeOwned + Default>(storage: &dyn Storage, key: &str) -> T {
+    let Some(serialized) = storage.load(key) else {
+        return T::default();
+    };
+    ron::from_str(&serialized).unwrap_or_else(|err| {
+        warn!("Unable to read {}: {}", key, err);
+        T::default()
+    })
+}
+
+pub fn save_ron<T: Serialize>(storage: &dyn Storage, key: &str, value: &T) {
+    let result = ron::to_string(value)
+        .map_err(|err| err.to_string())
+        .and_then(|serialized| storage.save(key, &serialized));
+    if let Err(err) = result {
+        error!("Unable to save {}: {}", key, err);
+    }
+}
+
+#[derive(Resource)]
+pub struct GameStorage(pub Box<dyn Storage>);
+
+impl Default for GameStorage {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn default() -> Self {
+        Self(Box::new(DiskStorage))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn default() -> Self {
+        Self(Box::new(LocalStorage))
+    }
+}
+
+/// Every key is a `<key>.ron` file in `SAVE_DIR`, relative to the working directory, binary blobs are raw `<key>.bin` files.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct DiskStorage;
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIR: &str = "save";
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DiskStorage {
+    fn path(key: &str, extension: &str) -> std::path::PathBuf {
+        std::path::Path::new(SAVE_DIR).join(format!("{}.{}", key, extension))
+    }
+
+    fn write(path: std::path::PathBuf, contents: &[u8]) -> Result<(), String> {
+        std::fs::create_dir_all(SAVE_DIR)
+            .and_then(|_| std::fs::write(path, contents))
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for DiskStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(Self::path(key, "ron")).ok()
+    }
+
+    fn save(&self, key: &str, value: &str) -> Result<(), String> {
+        Self::write(Self::path(key, "ron"), value.as_bytes())
+    }
+
+    fn load_bytes(&self, key: &str) -> Option<Vec<u8>> {
+        std::fs::read(Self::path(key, "bin")).ok()
+    }
+
+    fn save_bytes(&self, key: &str, value: &[u8]) -> Result<(), String> {
+        Self::write(Self::path(key, "bin"), value)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorage;
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    fn get() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .ok_or("no window")?
+            .local_storage()
+            .map_err(|_| "local storage is not accessible")?
+            .ok_or("local storage is not available".to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for LocalStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        Self::get().ok()?.get_item(key).ok()?
+    }
+
+    fn save(&self, key: &str, value: &str) -> Result<(), String> {
+        Self::get()?.set_item(key, value).map_err(|_| format!("unable to write {} to local storage", key))
+    }
+}
+
+/// Keeps everything in memory, used by the headless harness.
+#[cfg(feature = "headless")]
+#[derive(Default)]
+pub struct MemoryStorage {
+    values: Mutex<HashMap<String, String>>,
+}
+
+#[cfg(feature = "headless")]
+impl Storage for MemoryStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.values.lock().ok()?.get(key).cloned()
+    }
+
+    fn save(&self, key: &str, value: &str) -> Result<(), String> {
+        self.values.lock()
+            .map_err(|err| err.to_string())?
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
-use bevy::{prelude::*};
+use bevy::{prelude::*, ecs::system::SystemParam};
 
-use crate::{GameState, GameResources, player::{Rotation, LeftSki, RightSki, get_graphics, Player}, Alive, yeti::Yeti, animation::Animation};
+use crate::{GameResources, player::{Rotation, LeftSki, RightSki, get_graphics, Player}, Alive, yeti::Yeti, simulation::SimulationSet};
 
 
 #[derive(Component)]
@@ -9,6 +9,37 @@ pub struct StunEffect;
 #[derive(Component)]
 pub struct Stun(pub Timer);
 
+type StunnablePlayer = (With<Alive>, With<Player>, Without<Yeti>);
+type PlayerSkis = (Or<(With<LeftSki>, With<RightSki>)>, Without<Yeti>, Without<Player>);
+
+/// Everything that can be stunned, woken up entities are restored right away, `RemovedComponents` would not survive between fixed ticks.
+#[derive(SystemParam)]
+pub struct Stunnables<'w, 's> {
+    game_resources: Res<'w, GameResources>,
+    player_q: Query<'w, 's, (&'static mut Sprite, &'static Rotation), StunnablePlayer>,
+    skis_q: Query<'w, 's, &'static mut Visibility, PlayerSkis>,
+    yeti_q: Query<'w, 's, &'static mut Yeti, (With<Alive>, Without<Player>)>,
+}
+
+impl Stunnables<'_, '_> {
+    fn wake_up(&mut self, entity: Entity, children: &Children) {
+        if let Ok((mut sprite, rotation)) = self.player_q.get_mut(entity) {
+            let (sprite_rect, flip_x) = get_graphics(rotation.0, &self.game_resources);
+            sprite.rect = Some(sprite_rect);
+            sprite.flip_x = flip_x;
+            for &ch in children {
+                if let Ok(mut visibility) = self.skis_q.get_mut(ch) {
+                    *visibility = Visibility::Inherited;
+                }
+            }
+        };
+
+        if let Ok(mut yeti) = self.yeti_q.get_mut(entity) {
+            yeti.recover();
+        };
+    }
+}
+
 pub struct StunPlugin;
 
 impl Plugin for StunPlugin {
@@ -17,61 +48,29 @@ impl Plugin for StunPlugin {
             .add_systems(
                 (
                   update_stun,
-                  update_wake_up.after(update_stun)
-                ).in_set(OnUpdate(GameState::Playing)));
+                ).in_set(SimulationSet::Tick).in_schedule(CoreSchedule::FixedUpdate));
     }
 }
 
 pub fn update_stun(
     mut commands: Commands,
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
     mut stun_q: Query<(Entity, &Children, &mut Stun)>,
     stun_effect_q: Query<Entity, (Without<Stun>, With<StunEffect>)>,
+    mut stunnables: Stunnables,
 ) {
-    let dt = time.delta();
+    let dt = fixed_time.period;
     for (entity, children, mut stun) in stun_q.iter_mut() {
-        if stun.0.tick(dt).finished() {
-            commands.entity(entity).remove::<Stun>();
-            for &child in children.iter() {
-                if stun_effect_q.get(child).is_ok() {
-                    commands.entity(entity).remove_children(&[child]);
-                    commands.entity(child).despawn_recursive();
-                }
+        if !stun.0.tick(dt).finished() {
+            continue;
+        }
+        commands.entity(entity).remove::<Stun>();
+        for &child in children.iter() {
+            if stun_effect_q.get(child).is_ok() {
+                commands.entity(entity).remove_children(&[child]);
+                commands.entity(child).despawn_recursive();
             }
         }
+        stunnables.wake_up(entity, children);
     };
 }
-
-pub fn update_wake_up(
-    game_resources: Res<GameResources>,
-    mut woken_up: RemovedComponents<Stun>,
-    mut player_q: Query<(&mut Sprite, &Rotation, &Children), (With<Alive>, With<Player>, Without<Yeti>)>,
-    mut skis_q: Query<&mut Visibility, (Or<(With<LeftSki>, With<RightSki>)>, Without<Yeti>, Without<Player>)>,
-    mut yeti_q: Query<(&mut Sprite, &mut Animation, &mut Yeti), (With<Alive>, Without<Player>)>,
-) {
-    for entity_woken in woken_up.iter() {
-        if let Ok((mut sprite, rotation, children)) = player_q.get_mut(entity_woken) {
-            let (sprite_rect, flip_x) = get_graphics(rotation.0, &game_resources);
-            sprite.rect = Some(sprite_rect);
-            sprite.flip_x = flip_x;
-            for &ch in children {
-                if let Ok(mut visibility) = skis_q.get_mut(ch) {
-                    *visibility = Visibility::Inherited;
-                }
-            }
-        };
-
-        if let Ok((mut sprite, mut animation, mut yeti)) = yeti_q.get_mut(entity_woken) {
-            yeti.ignore_collisions.reset();
-            animation.set_frames(vec![
-                game_resources.yeti_run[0],
-                game_resources.yeti_run[1],
-                game_resources.yeti_run[0],
-                game_resources.yeti_run[2]
-            ]);
-            if let Some(rect) = animation.get_frame() {
-                sprite.rect = Some(rect);
-            }
-        };
-    }
-}
\ No newline at end of file
@@ -0,0 +1,170 @@
+use bevy::{prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    RaceSchedule,
+    SCALE_FACTOR,
+    despawn,
+    player::{Player, self},
+    uicontrols,
+    simulation::SimulationSet,
+};
+
+pub const SURFACE_Z_INDEX: f32 = 0.0;
+const MOGUL_SPACING: f32 = 12.0 * SCALE_FACTOR;
+/// Distance travelled over moguls during one full speed wobble.
+pub const MOGUL_WAVELENGTH: f32 = MOGUL_SPACING;
+const MOGUL_SIZE: (f32, f32) = (6.0 * SCALE_FACTOR, 3.0 * SCALE_FACTOR);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SurfaceType {
+    Ice,
+    Powder,
+    Moguls,
+}
+
+impl SurfaceType {
+    /// Multiplier of the turning speed.
+    pub fn grip(&self) -> f32 {
+        match self {
+            SurfaceType::Ice => 0.45,
+            SurfaceType::Powder => 0.8,
+            SurfaceType::Moguls => 0.9,
+        }
+    }
+
+    /// Multiplier of the snow friction, used by the physics profile.
+    pub fn friction(&self) -> f32 {
+        match self {
+            SurfaceType::Ice => 0.15,
+            SurfaceType::Powder => 6.0,
+            SurfaceType::Moguls => 1.5,
+        }
+    }
+
+    /// Multiplier of the acceleration, used by the arcade profile.
+    pub fn acceleration(&self) -> f32 {
+        match self {
+            SurfaceType::Ice => 1.2,
+            SurfaceType::Powder => 0.3,
+            SurfaceType::Moguls => 0.8,
+        }
+    }
+
+    /// Multiplier of the top speed, used by the arcade profile.
+    pub fn top_speed(&self) -> f32 {
+        match self {
+            SurfaceType::Ice => 1.0,
+            SurfaceType::Powder => 0.5,
+            SurfaceType::Moguls => 0.85,
+        }
+    }
+
+    /// Share of the speed lost while turning that is actually lost.
+    pub fn turn_speed_loss(&self) -> f32 {
+        match self {
+            SurfaceType::Ice => 0.05,
+            SurfaceType::Powder | SurfaceType::Moguls => 1.0,
+        }
+    }
+
+    /// Relative amplitude of the periodic speed change.
+    pub fn wobble(&self) -> f32 {
+        match self {
+            SurfaceType::Moguls => 0.3,
+            SurfaceType::Ice | SurfaceType::Powder => 0.0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            SurfaceType::Ice => Color::rgb(0.78, 0.88, 0.98),
+            SurfaceType::Powder => Color::rgb(1.0, 1.0, 1.0),
+            SurfaceType::Moguls => Color::rgb(0.9, 0.9, 0.96),
+        }
+    }
+}
+
+/// Ground area with its own friction and steering, it doesn't collide.
+#[derive(Component)]
+pub struct SurfacePatch {
+    pub kind: SurfaceType,
+    pub bounds: Rect,
+}
+
+pub struct SurfacePlugin;
+
+impl Plugin for SurfacePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(despawn::<SurfacePatch>.in_schedule(RaceSchedule::Teardown))
+            .add_system(
+                detect_surface
+                    .after(uicontrols::player_input)
+                    .before(player::update_player)
+                    .in_set(SimulationSet::Tick)
+                    .in_schedule(CoreSchedule::FixedUpdate)
+            );
+    }
+}
+
+pub fn spawn_surface(
+    commands: &mut Commands,
+    kind: SurfaceType,
+    bounds: Rect,
+) -> Entity {
+    let center = bounds.center();
+    let size = bounds.size();
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: kind.color(),
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_xyz(center.x, center.y, SURFACE_Z_INDEX),
+            ..default()
+        },
+        SurfacePatch { kind, bounds },
+    ))
+    .with_children(|parent| {
+        if kind != SurfaceType::Moguls {
+            return;
+        }
+        let columns = (size.x / MOGUL_SPACING) as usize;
+        let rows = (size.y / MOGUL_SPACING) as usize;
+        for row in 0..rows {
+            for column in 0..columns {
+                // every other row is shifted so the bumps are staggered
+                let shift = if row % 2 == 0 { 0.25 } else { 0.75 };
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.8, 0.82, 0.92),
+                        custom_size: Some(Vec2::new(MOGUL_SIZE.0, MOGUL_SIZE.1)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        (column as f32 + shift) * MOGUL_SPACING - size.x / 2.0,
+                        (row as f32 + 0.5) * MOGUL_SPACING - size.y / 2.0,
+                        0.05,
+                    ),
+                    ..default()
+                });
+            }
+        }
+    })
+    .id()
+}
+
+fn detect_surface(
+    mut player_q: Query<(&Transform, &mut Player)>,
+    patches_q: Query<&SurfacePatch>,
+) {
+    let Ok((transform, mut player)) = player_q.get_single_mut() else {
+        return;
+    };
+    let position = transform.translation.truncate();
+    player.surface = patches_q.iter()
+        .find(|patch| patch.bounds.contains(position))
+        .map(|patch| patch.kind);
+}
@@ -1,9 +1,10 @@
 use bevy::{prelude::*, math::vec2};
 
-use crate::{despawn, GameState, player::{Player, Rotation, PLAYER_Z_INDEX, get_standing_position_offset}, Alive, SCALE_FACTOR, cleanup, GameResources, yeti::{Yeti}, animation::Animation, stuneffect::Stun};
+use crate::{despawn, GameState, RaceSchedule, player::{Player, Rotation, PLAYER_Z_INDEX, get_standing_position_offset}, Alive, SCALE_FACTOR, cleanup, GameResources, yeti::{Yeti}, animation::Animation, stuneffect::Stun, jump::Airborne, surface::{SurfaceType, SURFACE_Z_INDEX}};
 
 
 const TRAIL_SIZE: (f32, f32) = (1.0 * SCALE_FACTOR, 1.0 * SCALE_FACTOR);
+const TRAIL_Z_INDEX: f32 = SURFACE_Z_INDEX + 0.1;
 
 #[derive(Component)]
 pub struct Trail;
@@ -19,7 +20,7 @@ impl Plugin for TrailPlugin {
                     leave_trail_player,
                     cleanup::<Trail>,
                 ).in_set(OnUpdate(GameState::Playing)))
-            .add_system(despawn::<Trail>.in_schedule(OnExit(GameState::GameOver)));
+            .add_system(despawn::<Trail>.in_schedule(RaceSchedule::Teardown));
     }
 }
 
@@ -28,36 +29,39 @@ fn leave_trail_yeti(
     game_resources: Res<GameResources>,
     yeti_q: Query<(&Transform, &Animation), (Changed<Transform>, With<Yeti>, With<Alive>, Without<Stun>)>,
 ) {
-    let Ok((transform, animation)) = yeti_q.get_single() else {
-        return;
-    };
-
-    if animation.timer.just_finished() &&
-        (animation.act_frame_index == 0 || animation.act_frame_index == 2)
-    {
-        commands.spawn((
-            SpriteBundle {
-                sprite: Sprite {
-                    custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
-                    rect: Some(game_resources.yeti_step),
+    for (transform, animation) in yeti_q.iter() {
+        if animation.timer.just_finished() &&
+            (animation.act_frame_index == 0 || animation.act_frame_index == 2)
+        {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
+                        rect: Some(game_resources.yeti_step),
+                        ..default()
+                    },
+                    texture: game_resources.image_handle.clone(),
+                    transform: Transform::from_xyz(transform.translation.x, transform.translation.y, PLAYER_Z_INDEX - 1.5),
                     ..default()
                 },
-                texture: game_resources.image_handle.clone(),
-                transform: Transform::from_xyz(transform.translation.x, transform.translation.y, PLAYER_Z_INDEX - 1.5),
-                ..default()
-            },
-            Trail
-        ));
+                Trail
+            ));
+        }
     }
 }
 
 fn leave_trail_player(
     mut commands: Commands,
-    player_q: Query<(&Transform, &Rotation), (Changed<Transform>, With<Player>, With<Alive>)>,
+    player_q: Query<(&Transform, &Rotation, &Player), (Changed<Transform>, With<Alive>, Without<Airborne>)>,
 ) {
-    let Ok((transform, rotation)) = player_q.get_single() else {
+    let Ok((transform, rotation, player)) = player_q.get_single() else {
         return;
     };
+    // the skis only touch the snow in the troughs between moguls
+    if player.surface == Some(SurfaceType::Moguls) && player.surface_phase() > 0.5 {
+        return;
+    }
+    let (color, size) = get_trail_mark(player.surface);
 
     let offsets = get_standing_position_offset(rotation.0);
 
@@ -65,14 +69,23 @@ fn leave_trail_player(
         commands.spawn((
             SpriteBundle {
                 sprite: Sprite {
-                    color: Color::rgb(0.8, 0.8, 0.9),
-                    custom_size: Some(Vec2::new(TRAIL_SIZE.0, TRAIL_SIZE.1)),
+                    color,
+                    custom_size: Some(size),
                     ..default()
                 },
-                transform: Transform::from_xyz(transform.translation.x + dx, transform.translation.y + dy, 0.),
+                transform: Transform::from_xyz(transform.translation.x + dx, transform.translation.y + dy, TRAIL_Z_INDEX),
                 ..default()
             },
             Trail
         ));
     }
-}
\ No newline at end of file
+}
+
+fn get_trail_mark(surface: Option<SurfaceType>) -> (Color, Vec2) {
+    match surface {
+        None => (Color::rgb(0.8, 0.8, 0.9), Vec2::new(TRAIL_SIZE.0, TRAIL_SIZE.1)),
+        Some(SurfaceType::Ice) => (Color::rgb(0.9, 0.95, 1.0), Vec2::new(TRAIL_SIZE.0 * 0.5, TRAIL_SIZE.1)),
+        Some(SurfaceType::Powder) => (Color::rgb(0.72, 0.74, 0.86), Vec2::new(TRAIL_SIZE.0 * 2.0, TRAIL_SIZE.1 * 1.5)),
+        Some(SurfaceType::Moguls) => (Color::rgb(0.75, 0.75, 0.85), Vec2::new(TRAIL_SIZE.0, TRAIL_SIZE.1)),
+    }
+}
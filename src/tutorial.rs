@@ -1,6 +1,6 @@
 use bevy::{prelude::*};
 
-use crate::{GameResources, despawn, GameState, uicontrols::{ControlScheme, ControlSchemeType}};
+use crate::{GameResources, despawn, GameState, RaceSchedule, not_pausing, uicontrols::{ControlScheme, ControlSchemeType}, controls::{Action, KeyBindings, key_name}};
 
 #[derive(Component)]
 pub struct TutorialText;
@@ -10,14 +10,16 @@ pub struct TutorialPlugin;
 impl Plugin for TutorialPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_system(setup.in_schedule(OnEnter(GameState::Playing)))
-            .add_system(despawn::<TutorialText>.in_schedule(OnExit(GameState::Playing)));
+            .add_system(setup.in_schedule(RaceSchedule::Setup))
+            .add_system(despawn::<TutorialText>.run_if(not_pausing).in_schedule(OnExit(GameState::Playing)))
+            .add_system(despawn::<TutorialText>.in_schedule(RaceSchedule::Teardown));
     }
 }
 
 fn setup(
     mut commands: Commands,
     control_scheme: Res<ControlScheme>,
+    key_bindings: Res<KeyBindings>,
     game_resources: Res<GameResources>,
 ) {
     let text_style = TextStyle {
@@ -27,8 +29,12 @@ fn setup(
     };
     let text_alignment = TextAlignment::Center;
     let text = match control_scheme.kind {
-        ControlSchemeType::Desktop => "Press\nA or D\nto turn",
-        ControlSchemeType::Mobile => "Press\nbuttons\nto turn"
+        ControlSchemeType::Desktop => format!(
+            "Press\n{} or {}\nto turn",
+            key_name(key_bindings.key(Action::SteerLeft)),
+            key_name(key_bindings.key(Action::SteerRight)),
+        ),
+        ControlSchemeType::Mobile => "Press\nbuttons\nto turn".to_string(),
     };
     commands.spawn((
         Text2dBundle {
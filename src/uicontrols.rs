@@ -1,18 +1,32 @@
-use bevy::{prelude::*};
+use bevy::{prelude::*, ecs::system::SystemParam};
 use os_info::Type;
+use serde::{Deserialize, Serialize};
 
-use crate::{GameState, despawn, GameResources, player::{Player}, Alive, SELECTED_BUTTON};
+use crate::{GameState, RaceSchedule, not_pausing, despawn, GameResources, player::{Player}, Alive, SELECTED_BUTTON, replay::ReplayPlayback, simulation::SimulationSet, controls::{Action, KeyBindings}};
+
+/// Stick travel ignored around the centre, the rest is rescaled to the whole steering range.
+const STICK_DEADZONE: f32 = 0.15;
+const STICK_STANCE_THRESHOLD: f32 = 0.5;
 
 #[derive(Component)]
 pub struct UiControls;
 
-#[derive(Component, Clone)]
+#[derive(Component, Clone, Debug, PartialEq)]
 pub enum UiControlType {
     Left,
     Right,
 }
 
-#[derive(PartialEq)]
+/// Posture held on top of steering, only the physics ski profile reacts to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SkiStance {
+    #[default]
+    Normal,
+    Tuck,
+    Brake,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ControlSchemeType {
     Mobile,
     Desktop,
@@ -56,42 +70,111 @@ impl Plugin for UiControlsPlugin {
             .insert_resource(ControlScheme {
                 kind: control_scheme_kind
             })
-            .add_system(setup_uicontrols.in_schedule(OnEnter(GameState::Playing)))
-            .add_system(despawn::<UiControls>.in_schedule(OnExit(GameState::Playing)))
-            .add_system(player_input.in_set(OnUpdate(GameState::Playing)));
+            .init_resource::<KeyBindings>()
+            .add_system(setup_uicontrols.in_schedule(RaceSchedule::Setup))
+            .add_system(despawn::<UiControls>.run_if(not_pausing).in_schedule(OnExit(GameState::Playing)))
+            .add_system(despawn::<UiControls>.in_schedule(RaceSchedule::Teardown))
+            .add_system(player_input.in_set(SimulationSet::Tick).in_schedule(CoreSchedule::FixedUpdate));
     }
 }
 
 
 pub fn player_input(
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    gamepad_input: GamepadInput,
     mut player_q: Query<&mut Player, With<Alive>>,
     interaction_query: Query<
         (&Interaction, &UiControlType),
         (With<Button>, With<UiControls>),
     >,
+    playback: Option<ResMut<ReplayPlayback>>,
 ) {
+    if let Some(mut playback) = playback {
+        let frame = playback.next_frame().cloned();
+        if let (Some(frame), Ok(mut player)) = (frame, player_q.get_single_mut()) {
+            player.control_type = frame.control_type;
+            player.steering = frame.steering;
+            player.stance = frame.stance;
+        }
+        return;
+    }
     let Ok(mut player) = player_q.get_single_mut() else {
         return;
     };
 
-    if keyboard_input.pressed(KeyCode::A) {
+    gamepad_input.steer(&mut player);
+
+    if key_bindings.pressed(&keyboard_input, Action::SteerLeft) {
         player.control_type = Some(UiControlType::Left);
+        player.steering = 1.0;
     }
-    if keyboard_input.pressed(KeyCode::D) {
+    if key_bindings.pressed(&keyboard_input, Action::SteerRight) {
         player.control_type = Some(UiControlType::Right);
+        player.steering = 1.0;
+    }
+    if key_bindings.pressed(&keyboard_input, Action::Tuck) {
+        player.stance = SkiStance::Tuck;
+    }
+    if key_bindings.pressed(&keyboard_input, Action::Brake) {
+        player.stance = SkiStance::Brake;
     }
 
     for (interaction, uicontrol_type) in &interaction_query {
         match *interaction {
             Interaction::Clicked | Interaction::Hovered => {
                 player.control_type = Some(uicontrol_type.clone());
+                player.steering = 1.0;
             },
             _ => {}
         }
     }
 }
 
+/// Sticks and buttons of every connected gamepad.
+#[derive(SystemParam)]
+pub struct GamepadInput<'w> {
+    gamepads: Res<'w, Gamepads>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+}
+
+impl GamepadInput<'_> {
+    /// The stick steers as far as it is tilted, the d-pad and triggers work like keys.
+    fn steer(&self, player: &mut Player) {
+        for gamepad in self.gamepads.iter() {
+            let stick_x = self.gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0);
+            let stick_y = self.gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.0);
+            let steering = analog_steering(stick_x);
+            if steering > player.steering {
+                player.control_type = Some(if stick_x < 0.0 { UiControlType::Left } else { UiControlType::Right });
+                player.steering = steering;
+            }
+            let button = |button_type| self.gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type));
+            if button(GamepadButtonType::DPadLeft) {
+                player.control_type = Some(UiControlType::Left);
+                player.steering = 1.0;
+            }
+            if button(GamepadButtonType::DPadRight) {
+                player.control_type = Some(UiControlType::Right);
+                player.steering = 1.0;
+            }
+            if button(GamepadButtonType::RightTrigger2) || stick_y < -STICK_STANCE_THRESHOLD {
+                player.stance = SkiStance::Tuck;
+            }
+            if button(GamepadButtonType::LeftTrigger2) || stick_y > STICK_STANCE_THRESHOLD {
+                player.stance = SkiStance::Brake;
+            }
+        }
+    }
+}
+
+/// Steering strength of a stick tilted to `stick_x`, quantized to the 1/255 steps a replay can store.
+fn analog_steering(stick_x: f32) -> f32 {
+    let steering = ((stick_x.abs() - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).clamp(0.0, 1.0);
+    (steering * 255.0).round() / 255.0
+}
+
 fn setup_uicontrols(
     mut commands: Commands,
     window: Query<&Window>,
@@ -1,21 +1,127 @@
-use std::{time::Duration, f32::consts::{FRAC_PI_2, FRAC_PI_8}};
+use std::{time::Duration, f32::consts::FRAC_PI_2};
 
 use bevy::{prelude::*, math::vec2};
-use rand::Rng;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
 
-use crate::{despawn, GameState, player::{PLAYER_Z_INDEX, Player, PLAYER_CAMERA_OFFSET, Velocity}, Alive, SCALE_FACTOR, GameResources, collidable::{Collidable, CollidableMovable}, debug::DebugMarker, animation::Animation, trail::Trail, stuneffect::{Stun}, obstacle::Obstacle};
+use crate::{despawn, GameState, RaceSchedule, player::{PLAYER_Z_INDEX, Player, PLAYER_CAMERA_OFFSET, Velocity, Falldown, Grabbed, Catched}, Alive, SCALE_FACTOR, SCREEN_WIDTH, SCREEN_HEIGHT, GameResources, collidable::{Collidable, CollidableMovable}, debug::DebugMarker, animation::Animation, trail::Trail, stuneffect::{Stun}, obstacle::YetiObstacles, course::CourseId, simulation::{SimulationSet, Interpolated}, spatialtree::SpatialTree, replay::ReplayPlayback, navigation::NavGrid};
 
 const SPEED: f32 = 48.0 * SCALE_FACTOR;
 const YETI_COLLIDABLE_DIMENSIONS: (f32, f32) = (4.0 * SCALE_FACTOR, 3.0 * SCALE_FACTOR);
 const YETI_COLLIDABLE_OFFSETS: (f32, f32) = (0.0 * SCALE_FACTOR, -2.0 * SCALE_FACTOR);
-pub const YETI_STUN_TIME: f32 = 0.8;
-const YETI_SPAWNER_TIMEOUT: (f32, f32) = (30.0, 3.0);
-const RAY_LENGHT: f32 = 400.0;
+const NAV_CELL_SIZE: f32 = 4.0 * SCALE_FACTOR;
+/// Space around the yeti and the player that is searched, lets the path go around wide obstacles.
+const NAV_MARGIN: f32 = 40.0 * SCALE_FACTOR;
+/// Further than this the obstacles in between don't matter yet and the yeti runs straight.
+const NAV_RANGE: f32 = 240.0 * SCALE_FACTOR;
+const REPLAN_SECONDS: f32 = 0.25;
+/// How fast the velocity turns towards the desired direction, per second.
+const STEERING_RESPONSE: f32 = 20.0;
+/// Closer than this the yeti sprints at the player or stops to taunt a fallen one.
+const SPRINT_DISTANCE: f32 = 50.0 * SCALE_FACTOR;
+/// Pack members push each other away when closer than this.
+const SEPARATION_DISTANCE: f32 = 20.0 * SCALE_FACTOR;
+const SEPARATION_WEIGHT: f32 = 1.5;
+/// Largest sideways offset of a pack member's aim, it shrinks to nothing close to the player.
+const FLANK_DISTANCE: f32 = 30.0 * SCALE_FACTOR;
+/// Distance travelled between two waves, the first one comes after the spawner timeout.
+const WAVE_DISTANCE: f32 = 1500.0 * SCALE_FACTOR;
+/// Every this much distance travelled one more yeti joins the wave.
+const PACK_GROWTH_DISTANCE: f32 = 3000.0 * SCALE_FACTOR;
+const MAX_PACK_SIZE: usize = 4;
+/// Rubber-banding scales the speed between these factors, from `SPRINT_DISTANCE` up to `RUBBER_BAND_DISTANCE`.
+const RUBBER_BAND_FACTORS: (f32, f32) = (0.8, 1.6);
+const RUBBER_BAND_DISTANCE: f32 = 150.0 * SCALE_FACTOR;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Nightmare => "Nightmare",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Nightmare,
+            Difficulty::Nightmare => Difficulty::Easy,
+        }
+    }
+
+    pub fn yeti_speed(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 40.0 * SCALE_FACTOR,
+            Difficulty::Normal => SPEED,
+            Difficulty::Hard => 54.0 * SCALE_FACTOR,
+            Difficulty::Nightmare => 62.0 * SCALE_FACTOR,
+        }
+    }
+
+    /// Seconds before the footprints show up and then before the yeti itself does.
+    pub fn spawner_timeout(&self) -> (f32, f32) {
+        match self {
+            Difficulty::Easy => (45.0, 4.0),
+            Difficulty::Normal => (30.0, 3.0),
+            Difficulty::Hard => (20.0, 2.5),
+            Difficulty::Nightmare => (10.0, 2.0),
+        }
+    }
+
+    /// Seconds after spawning and after a stun when obstacles and the player are ignored.
+    pub fn ignore_collisions_time(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 2.0,
+            Difficulty::Normal => 1.5,
+            Difficulty::Hard => 1.0,
+            Difficulty::Nightmare => 0.5,
+        }
+    }
+
+    pub fn stun_time(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.2,
+            Difficulty::Normal => 0.8,
+            Difficulty::Hard => 0.6,
+            Difficulty::Nightmare => 0.4,
+        }
+    }
+}
+
+/// Chosen in the menu, a replay uses the settings it was recorded with.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct YetiSettings {
+    pub difficulty: Difficulty,
+    /// Faster when far behind the player, slower when close.
+    pub rubber_banding: bool,
+}
+
+/// Waypoints towards the player, replanned a few times per second, a yeti without one runs straight at its target.
 #[derive(Component)]
-pub enum YetiAi {
-    ChaseDirect,
-    ChaseAvoidObstacles,
+pub struct YetiPath {
+    waypoints: Vec<Vec2>,
+    replan: Timer,
+}
+
+impl Default for YetiPath {
+    fn default() -> Self {
+        Self {
+            waypoints: vec![],
+            replan: Timer::from_seconds(REPLAN_SECONDS, TimerMode::Repeating),
+        }
+    }
 }
 
 pub enum YetiSpawnPhase {
@@ -29,12 +135,104 @@ pub enum YetiSpawnPhase {
 pub struct YetiSpawner {
     pub phase: YetiSpawnPhase,
     pub timer: Timer,
+    rng: StdRng,
+    settings: YetiSettings,
+    /// The camera has to get below this before the next wave comes.
+    next_wave_y: f32,
+}
+
+/// Spawned as part of a wave, `flank` from -1 to 1 says from which side it comes at the player.
+#[derive(Component)]
+pub struct PackMember {
+    pub flank: f32,
+}
+
+/// The player while a yeti can still go after them.
+type ChasedPlayer = (With<Player>, With<Alive>, Without<Yeti>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum YetiState {
+    /// Follows the player, sprints once close and rested.
+    Stalk,
+    Sprint,
+    /// Slow after waking up from a `Stun`.
+    Recover,
+    /// Stands and mocks a fallen player before sprinting at them.
+    Taunt,
+    /// Plays the eating animation, the player is `Catched` when it is over.
+    Eat,
+}
+
+impl YetiState {
+    fn duration(&self) -> f32 {
+        match self {
+            YetiState::Stalk => 1.0,
+            YetiState::Sprint => 1.5,
+            YetiState::Recover => 1.0,
+            YetiState::Taunt => 0.8,
+            YetiState::Eat => 0.0,
+        }
+    }
+
+    /// Multiplier of `Yeti::speed`.
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            YetiState::Stalk => 0.85,
+            YetiState::Sprint => 1.35,
+            YetiState::Recover => 0.4,
+            YetiState::Taunt | YetiState::Eat => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum YetiPose {
+    Run,
+    Side,
+    Taunt,
+    Eat,
 }
 
 #[derive(Component)]
 pub struct Yeti {
     pub ignore_collisions: Timer,
-    pub speed: f32
+    pub speed: f32,
+    pub stun_time: f32,
+    rubber_banding: bool,
+    state: YetiState,
+    state_timer: Timer,
+    pose: Option<YetiPose>,
+}
+
+impl Yeti {
+    pub fn new(settings: &YetiSettings) -> Self {
+        let difficulty = settings.difficulty;
+        Self {
+            ignore_collisions: Timer::from_seconds(difficulty.ignore_collisions_time(), TimerMode::Once),
+            speed: difficulty.yeti_speed(),
+            stun_time: difficulty.stun_time(),
+            rubber_banding: settings.rubber_banding,
+            state: YetiState::Stalk,
+            state_timer: Timer::from_seconds(YetiState::Stalk.duration(), TimerMode::Once),
+            pose: None,
+        }
+    }
+
+    pub fn state(&self) -> YetiState {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: YetiState) {
+        self.state = state;
+        self.state_timer = Timer::from_seconds(state.duration(), TimerMode::Once);
+    }
+
+    /// Called when the stun is over, the graphics are refreshed from the new state.
+    pub fn recover(&mut self) {
+        self.ignore_collisions.reset();
+        self.set_state(YetiState::Recover);
+        self.pose = None;
+    }
 }
 
 pub struct YetiPlugin;
@@ -45,140 +243,226 @@ struct DebugYetiVelocity;
 impl Plugin for YetiPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<YetiSettings>()
             .insert_resource(YetiSpawner {
                 phase: YetiSpawnPhase::Idle,
-                timer: Timer::from_seconds(YETI_SPAWNER_TIMEOUT.0, TimerMode::Once)
+                timer: Timer::from_seconds(Difficulty::default().spawner_timeout().0, TimerMode::Once),
+                rng: StdRng::seed_from_u64(0),
+                settings: YetiSettings::default(),
+                next_wave_y: 0.0,
             })
+            .add_system(seed_spawner.in_schedule(RaceSchedule::Setup))
             .add_systems(
                 (
                     update_spawner,
+                    update_yeti_state.before(yeti_ai),
                     yeti_ai,
                     update_yeti.after(yeti_ai),
+                ).in_set(SimulationSet::Tick).in_schedule(CoreSchedule::FixedUpdate))
+            .add_systems(
+                (
+                    update_yeti_graphics,
+                    finish_eating,
                 ).in_set(OnUpdate(GameState::Playing)))
             .add_systems(
                 (
                     despawn::<Yeti>,
                     reset_spawner
-                ).in_schedule(OnExit(GameState::GameOver)));
+                ).in_schedule(RaceSchedule::Teardown));
     }
 }
 
 
 fn yeti_ai(
-    obstacles_q: Query<&Collidable, (With<Obstacle>, Without<Yeti>)>,
-    mut yeti_q: Query<(&Transform, &Yeti, &mut Velocity, &YetiAi), (Without<Player>, Without<Stun>)>,
+    fixed_time: Res<FixedTime>,
+    obstacles_q: Query<&Collidable, YetiObstacles>,
+    mut yeti_q: Query<(Entity, &Collidable, &Yeti, &mut Velocity, Option<&mut YetiPath>, Option<&PackMember>), (Without<Player>, Without<Stun>)>,
+    pack_q: Query<(Entity, &Collidable), With<Yeti>>,
     player_q: Query<&Transform, (With<Player>, Without<Yeti>)>,
+    spatial_tree: Res<SpatialTree>,
 ) {
-    let Ok((
-        transform,
-        yeti,
-        mut velocity,
-        yeti_ai,
-    )) = yeti_q.get_single_mut() else {
-        return;
-    };
-    let Ok(
-        player_transform,
-    ) = player_q.get_single() else {
-        velocity.0 = Vec2::ZERO;
-        return;
-    };
+    let player_transform = player_q.get_single().ok();
+    // stunned yetis are still in the way of the others
+    let pack: Vec<(Entity, Vec2)> = pack_q.iter()
+        .map(|(entity, collidable)| (entity, collidable.bounds().center()))
+        .collect();
 
-    match yeti_ai {
-        YetiAi::ChaseDirect => {
-            velocity.0 = (player_transform.translation.truncate() - transform.translation.truncate()).normalize() * yeti.speed;
-        },
-        YetiAi::ChaseAvoidObstacles => {
-            let position = transform.translation.truncate();
-            let facing_vec = player_transform.translation.truncate() - position;
-            let rotation = facing_vec.y.atan2(facing_vec.x);
-            let ray_length = facing_vec.length().min(RAY_LENGHT);
-
-            for i in 0..16 {
-                let angle_offset = rotation + (FRAC_PI_8 / 2.0 * i as f32);
-                let angle_offset_neg = rotation - (FRAC_PI_8 / 2.0 * i as f32);
-                let ray = vec2(angle_offset.cos(), angle_offset.sin());
-                let ray_neg = vec2(angle_offset_neg.cos(), angle_offset_neg.sin());
-
-                let mut intersect = false;
-                let mut intersect_neg = false;
-                for collidable_obstacle in obstacles_q.iter() {
-                    if !intersect {
-                        if collidable_obstacle.intersect_line(
-                            position,
-                            position + ray * ray_length
-                        ).is_some() {
-                            intersect = true;
-                        };
-                    }
-                    if !intersect_neg {
-                        if collidable_obstacle.intersect_line(
-                            position,
-                            position + ray_neg * ray_length
-                        ).is_some() {
-                            intersect_neg = true;
-                        };
-                    }
-                    if intersect && intersect_neg {
-                        break;
-                    }
-                };
-                if !intersect {
-                    if i == 0 {
-                        println!("no correction necessary at angle {}", angle_offset);
-                    } else {
-                        println!("found angle {} at offset {}", angle_offset, i);
-                    }
-                    velocity.0 = ray * yeti.speed;
-                    return;
+    for (entity, collidable, yeti, mut velocity, path, pack_member) in yeti_q.iter_mut() {
+        let Some(player_transform) = player_transform else {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        };
+        // the collision box is what has to fit between obstacles, so it is the one being navigated
+        let position = collidable.bounds().center();
+        let player_position = player_transform.translation.truncate();
+        let distance = position.distance(player_position);
+        let target = match pack_member {
+            Some(pack_member) => {
+                let side = (player_position - position).perp().normalize_or_zero();
+                player_position + side * pack_member.flank * (distance * 0.5).min(FLANK_DISTANCE)
+            },
+            None => player_position,
+        };
+
+        let direction = match path {
+            Some(mut path) if distance < NAV_RANGE => {
+                if path.replan.tick(fixed_time.period).just_finished() || path.waypoints.is_empty() {
+                    let area = Rect::from_corners(position, target).inset(NAV_MARGIN);
+                    let obstacles = spatial_tree.overlapping(area, |entity| obstacles_q.get(entity).ok()).into_iter()
+                        .filter_map(|entity| obstacles_q.get(entity).ok())
+                        .map(|obstacle| obstacle.bounds());
+                    let clearance = vec2(YETI_COLLIDABLE_DIMENSIONS.0, YETI_COLLIDABLE_DIMENSIONS.1);
+                    let grid = NavGrid::new(area, NAV_CELL_SIZE, clearance, obstacles);
+                    path.waypoints = grid.find_path(position, target).unwrap_or_default();
                 }
-                if !intersect_neg {
-                    println!("found negative angle {} at offset {}", angle_offset_neg, -i);
-                    velocity.0 = ray_neg * yeti.speed;
-                    return;
+                while path.waypoints.len() > 1 && path.waypoints[0].distance(position) < NAV_CELL_SIZE {
+                    path.waypoints.remove(0);
                 }
-            }
+                // the last waypoint is where the player was when planning, the live position is used instead
+                let waypoint = match path.waypoints.as_slice() {
+                    [next, _, ..] => *next,
+                    _ => target,
+                };
+                (waypoint - position).normalize_or_zero()
+            },
+            _ => (target - position).normalize_or_zero(),
+        };
+        let direction = (direction + get_separation(entity, position, &pack) * SEPARATION_WEIGHT).normalize_or_zero();
+
+        let rubber_band = if yeti.rubber_banding { get_rubber_band(distance) } else { 1.0 };
+        let speed = yeti.speed * yeti.state.speed_factor() * rubber_band;
+        if speed == 0.0 {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
+        let response = (STEERING_RESPONSE * fixed_time.period.as_secs_f32()).min(1.0);
+        velocity.0 = velocity.0.lerp(direction * speed, response).clamp_length_max(speed);
+    }
+}
+
+/// Push away from the other yetis, stronger the closer they are.
+fn get_separation(entity: Entity, position: Vec2, pack: &[(Entity, Vec2)]) -> Vec2 {
+    pack.iter()
+        .filter(|(other, _)| *other != entity)
+        .map(|(_, other_position)| position - *other_position)
+        .filter(|away| away.length() < SEPARATION_DISTANCE)
+        .map(|away| away.normalize_or_zero() * (1.0 - away.length() / SEPARATION_DISTANCE))
+        .sum()
+}
+
+fn get_rubber_band(distance: f32) -> f32 {
+    let t = ((distance - SPRINT_DISTANCE) / (RUBBER_BAND_DISTANCE - SPRINT_DISTANCE)).clamp(0.0, 1.0);
+    RUBBER_BAND_FACTORS.0 + (RUBBER_BAND_FACTORS.1 - RUBBER_BAND_FACTORS.0) * t
+}
+
+fn update_yeti_state(
+    fixed_time: Res<FixedTime>,
+    mut yeti_q: Query<(&Transform, &mut Yeti), Without<Stun>>,
+    player_q: Query<(&Transform, Option<&Falldown>, Option<&Stun>), ChasedPlayer>,
+) {
+    let player = player_q.get_single().ok();
+    for (transform, mut yeti) in yeti_q.iter_mut() {
+        let finished = yeti.state_timer.tick(fixed_time.period).finished();
+        let Some((player_transform, falldown, stun)) = player else {
+            continue;
+        };
+        let close = transform.translation.truncate().distance(player_transform.translation.truncate()) < SPRINT_DISTANCE;
+        let player_down = falldown.is_some() || stun.is_some();
+
+        let next_state = match yeti.state {
+            YetiState::Stalk if close && player_down => Some(YetiState::Taunt),
+            YetiState::Stalk if close && finished => Some(YetiState::Sprint),
+            YetiState::Sprint | YetiState::Recover if finished => Some(YetiState::Stalk),
+            YetiState::Taunt if finished => Some(YetiState::Sprint),
+            _ => None,
+        };
+        if let Some(next_state) = next_state {
+            yeti.set_state(next_state);
         }
+    }
+}
+
+/// Picks the frames for the state, running mostly sideways uses the side frames mirrored by direction.
+fn update_yeti_graphics(
+    game_resources: Res<GameResources>,
+    mut yeti_q: Query<(&mut Yeti, &Velocity, &mut Animation, &mut Sprite), Without<Stun>>,
+) {
+    for (mut yeti, velocity, mut animation, mut sprite) in yeti_q.iter_mut() {
+        let pose = match yeti.state {
+            YetiState::Eat => YetiPose::Eat,
+            YetiState::Taunt => YetiPose::Taunt,
+            _ if velocity.0.x.abs() > velocity.0.y.abs() => YetiPose::Side,
+            _ => YetiPose::Run,
+        };
+        sprite.flip_x = pose == YetiPose::Side && velocity.0.x < 0.0;
+        if yeti.pose == Some(pose) {
+            continue;
+        }
+        yeti.pose = Some(pose);
+        *animation = match pose {
+            YetiPose::Run => Animation::new(get_run_frames(&game_resources), TimerMode::Repeating),
+            YetiPose::Side => Animation::new(game_resources.yeti_side.clone(), TimerMode::Repeating),
+            YetiPose::Taunt => Animation::new(game_resources.yeti_taunt.clone(), TimerMode::Repeating),
+            YetiPose::Eat => Animation::new(game_resources.yeti_eat.clone(), TimerMode::Once),
+        };
+        sprite.rect = animation.get_frame();
+    }
+}
+
+fn finish_eating(
+    mut commands: Commands,
+    yeti_q: Query<(&Yeti, &Animation)>,
+    player_q: Query<Entity, With<Grabbed>>,
+) {
+    let Ok(entity) = player_q.get_single() else {
+        return;
     };
+    if yeti_q.iter().any(|(yeti, animation)| yeti.state == YetiState::Eat && animation.finished()) {
+        commands.entity(entity).remove::<Grabbed>().insert(Catched);
+    }
+}
+
+fn get_run_frames(game_resources: &GameResources) -> Vec<Rect> {
+    vec![
+        game_resources.yeti_run[0],
+        game_resources.yeti_run[1],
+        game_resources.yeti_run[0],
+        game_resources.yeti_run[2]
+    ]
 }
 
 pub fn update_yeti(
-    timer: Res<Time>,
+    fixed_time: Res<FixedTime>,
     mut yeti_q: Query<&mut Yeti, Without<Stun>>,
 ) {
-    let Ok(mut yeti) = yeti_q.get_single_mut() else {
-        return;
-    };
-    let dt = timer.delta();
-    yeti.ignore_collisions.tick(dt);
+    for mut yeti in yeti_q.iter_mut() {
+        yeti.ignore_collisions.tick(fixed_time.period);
+    }
 }
 
+/// Spawns around the view a locked camera has on the simulated player, so neither the window nor the frame rate changes a race.
 pub fn update_spawner(
-    timer: Res<Time>,
+    fixed_time: Res<FixedTime>,
     mut commands: Commands,
     mut yeti_spawner: ResMut<YetiSpawner>,
-    window: Query<&Window>,
     game_resources: Res<GameResources>,
-    camera_q: Query<&Transform, With<Camera>>,
+    player_q: Query<&Interpolated, With<Player>>,
 ) {
-    let Ok(window) = window.get_single() else {
-        return;
-    };
-    let Ok(camera_transform) = camera_q.get_single() else {
+    let Ok(player) = player_q.get_single() else {
         return;
     };
-    if !yeti_spawner.timer.tick(timer.delta()).finished() {
+    let view = player.current() - vec2(0.0, PLAYER_CAMERA_OFFSET);
+    if !yeti_spawner.timer.tick(fixed_time.period).finished() {
         return;
     }
-    let mut rng = rand::thread_rng();
     let next_phase = match yeti_spawner.phase {
         YetiSpawnPhase::Idle => {
             YetiSpawnPhase::Step
         },
         YetiSpawnPhase::Step => {
-            let mut x = -window.width() * 2.0;
-            let y = camera_transform.translation.y - window.height();
-            while x < window.width() * 2.0 {
+            let mut x = -SCREEN_WIDTH * 2.0;
+            let y = view.y - SCREEN_HEIGHT;
+            while x < SCREEN_WIDTH * 2.0 {
                 commands.spawn((
                     SpriteBundle {
                         sprite: Sprite {
@@ -194,62 +478,28 @@ pub fn update_spawner(
                 ));
                 x += game_resources.sprite_size;
             }
-            yeti_spawner.timer.set_duration(Duration::from_secs_f32(YETI_SPAWNER_TIMEOUT.1));
+            let timeout = yeti_spawner.settings.difficulty.spawner_timeout().1;
+            yeti_spawner.timer.set_duration(Duration::from_secs_f32(timeout));
             YetiSpawnPhase::Spawning
         },
         YetiSpawnPhase::Spawning => {
-            let x = camera_transform.translation.x + window.width() / 2.0;
-            let y = camera_transform.translation.y + rng.gen_range(0.0..(window.height() / 2.0 - PLAYER_CAMERA_OFFSET));
-
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
-                        rect: Some(game_resources.yeti_run[0]),
-                        ..default()
-                    },
-                    texture: game_resources.image_handle.clone(),
-                    transform: Transform::from_xyz(x, y, PLAYER_Z_INDEX + 0.5),
-                    ..default()
-                },
-                CollidableMovable,
-                Collidable::new(
-                    0., 0.,
-                    YETI_COLLIDABLE_DIMENSIONS.0, YETI_COLLIDABLE_DIMENSIONS.1,
-                    YETI_COLLIDABLE_OFFSETS.0, YETI_COLLIDABLE_OFFSETS.1
-                ),
-                Alive,
-                Velocity(Vec2::ZERO),
-                Animation::new(vec![
-                    game_resources.yeti_run[0],
-                    game_resources.yeti_run[1],
-                    game_resources.yeti_run[0],
-                    game_resources.yeti_run[2]
-                ], TimerMode::Repeating),
-                Yeti {
-                    ignore_collisions: Timer::from_seconds(1.5, TimerMode::Once),
-                    speed: SPEED
-                },
-                YetiAi::ChaseDirect,
-            ))
-            .with_children(|parent| {
-                parent.spawn((
-                    SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::rgb(0.25, 0.25, 0.75),
-                            custom_size: Some(Vec2::new(YETI_COLLIDABLE_DIMENSIONS.0 * 2.0, YETI_COLLIDABLE_DIMENSIONS.1 * 2.0)),
-                            ..default()
-                        },
-                        transform: Transform::from_xyz(YETI_COLLIDABLE_OFFSETS.0, YETI_COLLIDABLE_OFFSETS.1, 1.0),
-                        visibility: Visibility::Hidden,
-                        ..default()
-                    },
-                    DebugMarker
-                ));
-            });
-            yeti_spawner.timer.paused();
+            let distance = (-view.y).max(0.0);
+            let pack_size = (1 + (distance / PACK_GROWTH_DISTANCE) as usize).min(MAX_PACK_SIZE);
+            let settings = yeti_spawner.settings;
+            for i in 0..pack_size {
+                // every other member comes from the left edge
+                let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+                let x = view.x + side * SCREEN_WIDTH / 2.0;
+                let y = view.y + yeti_spawner.rng.gen_range(0.0..(SCREEN_HEIGHT / 2.0 - PLAYER_CAMERA_OFFSET));
+                let flank = if pack_size == 1 { 0.0 } else { -1.0 + 2.0 * i as f32 / (pack_size - 1) as f32 };
+
+                let yeti = spawn_yeti(&mut commands, &game_resources, &settings, x, y);
+                commands.entity(yeti).insert(PackMember { flank });
+            }
+            yeti_spawner.next_wave_y = view.y - WAVE_DISTANCE;
             YetiSpawnPhase::Completed
         },
+        YetiSpawnPhase::Completed if view.y < yeti_spawner.next_wave_y => YetiSpawnPhase::Step,
         YetiSpawnPhase::Completed => YetiSpawnPhase::Completed
     };
 
@@ -257,9 +507,71 @@ pub fn update_spawner(
     yeti_spawner.timer.reset();
 }
 
+pub fn spawn_yeti(
+    commands: &mut Commands,
+    game_resources: &GameResources,
+    settings: &YetiSettings,
+    x: f32,
+    y: f32,
+) -> Entity {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(vec2(game_resources.sprite_size, game_resources.sprite_size)),
+                rect: Some(game_resources.yeti_run[0]),
+                ..default()
+            },
+            texture: game_resources.image_handle.clone(),
+            transform: Transform::from_xyz(x, y, PLAYER_Z_INDEX + 0.5),
+            ..default()
+        },
+        CollidableMovable,
+        Interpolated::new(vec2(x, y)),
+        Collidable::new(
+            0., 0.,
+            YETI_COLLIDABLE_DIMENSIONS.0, YETI_COLLIDABLE_DIMENSIONS.1,
+            YETI_COLLIDABLE_OFFSETS.0, YETI_COLLIDABLE_OFFSETS.1
+        ),
+        Alive,
+        Velocity(Vec2::ZERO),
+        Animation::new(get_run_frames(game_resources), TimerMode::Repeating),
+        Yeti::new(settings),
+        YetiPath::default(),
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.25, 0.25, 0.75),
+                    custom_size: Some(Vec2::new(YETI_COLLIDABLE_DIMENSIONS.0 * 2.0, YETI_COLLIDABLE_DIMENSIONS.1 * 2.0)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(YETI_COLLIDABLE_OFFSETS.0, YETI_COLLIDABLE_OFFSETS.1, 1.0),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DebugMarker
+        ));
+    })
+    .id()
+}
+
+fn seed_spawner(
+    mut yeti_spawner: ResMut<YetiSpawner>,
+    course_id: Option<Res<CourseId>>,
+    yeti_settings: Res<YetiSettings>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    let seed = course_id.map(|course_id| course_id.seed()).unwrap_or_default();
+    yeti_spawner.rng = StdRng::seed_from_u64(seed);
+    yeti_spawner.settings = playback.map_or(*yeti_settings, |playback| playback.yeti_settings());
+    yeti_spawner.timer = Timer::from_seconds(yeti_spawner.settings.difficulty.spawner_timeout().0, TimerMode::Once);
+}
+
 fn reset_spawner(
     mut yeti_spawner: ResMut<YetiSpawner>,
 ) {
     yeti_spawner.phase = YetiSpawnPhase::Idle;
-    yeti_spawner.timer = Timer::from_seconds(YETI_SPAWNER_TIMEOUT.0, TimerMode::Once);
-}
\ No newline at end of file
+    let timeout = yeti_spawner.settings.difficulty.spawner_timeout().0;
+    yeti_spawner.timer = Timer::from_seconds(timeout, TimerMode::Once);
+}
@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use prashan::{
+    controls::{Action, KeyBindings},
+    headless::HeadlessApp,
+    player::Rotation,
+};
+
+#[test]
+fn binding_taken_key_swaps_actions() {
+    let mut key_bindings = KeyBindings::default();
+    assert_eq!(key_bindings.key(Action::SteerLeft), KeyCode::A);
+
+    key_bindings.bind(Action::SteerLeft, KeyCode::D);
+    assert_eq!(key_bindings.key(Action::SteerLeft), KeyCode::D);
+    assert_eq!(key_bindings.key(Action::SteerRight), KeyCode::A);
+}
+
+#[test]
+fn binding_debug_key_leaves_hidden_actions_alone() {
+    let mut key_bindings = KeyBindings::default();
+    key_bindings.bind(Action::Brake, KeyCode::U);
+    assert_eq!(key_bindings.key(Action::DebugStun), KeyCode::U);
+    assert_eq!(key_bindings.action(KeyCode::W), None);
+
+    let mut keyboard_input = Input::<KeyCode>::default();
+    keyboard_input.press(KeyCode::U);
+    assert!(key_bindings.just_pressed(&keyboard_input, Action::Brake));
+    assert!(!key_bindings.just_pressed(&keyboard_input, Action::DebugStun));
+}
+
+#[test]
+fn rebound_key_steers_player() {
+    let mut headless = HeadlessApp::new();
+    let mut key_bindings = KeyBindings::default();
+    key_bindings.bind(Action::SteerLeft, KeyCode::Q);
+    headless.app.insert_resource(key_bindings);
+    headless.start_race();
+    let player = headless.player().expect("player is spawned");
+
+    headless.app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::A);
+    headless.run_until(10, |_| false);
+    assert_eq!(headless.app.world.get::<Rotation>(player).unwrap().0, 0.0);
+
+    headless.app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Q);
+    headless.run_until(10, |_| false);
+    assert!(headless.app.world.get::<Rotation>(player).unwrap().0 < 0.0);
+}
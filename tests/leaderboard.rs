@@ -0,0 +1,35 @@
+use prashan::{
+    course::CourseId,
+    leaderboard::{Leaderboard, LeaderboardEntry, LEADERBOARD_SIZE},
+};
+
+fn entry(name: &str, time: f32) -> LeaderboardEntry {
+    LeaderboardEntry {
+        name: name.to_string(),
+        time,
+    }
+}
+
+#[test]
+fn keeps_fastest_times_per_course() {
+    let mut leaderboard = Leaderboard::default();
+    let course_id = CourseId::Seed(42);
+    let other_course_id = CourseId::Course("courses/first.course.ron".to_string());
+
+    for i in 0..LEADERBOARD_SIZE {
+        assert_eq!(leaderboard.insert(&course_id, entry("AAA", 20.0 + i as f32)), Some(i));
+    }
+    assert!(!leaderboard.qualifies(&course_id, 40.0));
+    assert_eq!(leaderboard.insert(&course_id, entry("SLO", 40.0)), None);
+
+    assert!(leaderboard.qualifies(&course_id, 10.0));
+    assert_eq!(leaderboard.insert(&course_id, entry("FST", 10.0)), Some(0));
+    let entries = leaderboard.entries(&course_id);
+    assert_eq!(entries.len(), LEADERBOARD_SIZE);
+    assert_eq!(entries[0], entry("FST", 10.0));
+    assert_eq!(entries.last().map(|entry| entry.time), Some(28.0));
+
+    assert!(leaderboard.entries(&other_course_id).is_empty());
+    assert!(leaderboard.qualifies(&other_course_id, 999.0));
+}
+
@@ -0,0 +1,21 @@
+use prashan::mixer::{AudioMixer, Channel};
+
+#[test]
+fn master_channel_scales_and_mutes_the_others() {
+    let mut mixer = AudioMixer::default();
+    mixer.sfx.volume = 0.5;
+    assert_eq!(mixer.volume(Channel::Sfx), 0.5);
+
+    mixer.master.volume = 0.5;
+    assert_eq!(mixer.volume(Channel::Sfx), 0.25);
+
+    mixer.toggle_mute(Channel::Music);
+    assert_eq!(mixer.volume(Channel::Music), 0.0);
+    assert_eq!(mixer.volume(Channel::Sfx), 0.25);
+
+    mixer.toggle_mute(Channel::Music);
+    mixer.toggle_mute(Channel::Master);
+    assert_eq!(mixer.volume(Channel::Music), 0.0);
+    assert_eq!(mixer.volume(Channel::Sfx), 0.0);
+}
+
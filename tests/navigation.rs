@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use prashan::navigation::NavGrid;
+
+const CELL_SIZE: f32 = 16.0;
+const AREA: Rect = Rect { min: Vec2::new(-320.0, -320.0), max: Vec2::new(320.0, 320.0) };
+
+fn wall() -> Rect {
+    Rect::new(-100.0, -10.0, 100.0, 10.0)
+}
+
+#[test]
+fn open_field_is_a_straight_line() {
+    let grid = NavGrid::new(AREA, CELL_SIZE, Vec2::ZERO, []);
+    let goal = Vec2::new(100.0, 200.0);
+
+    assert_eq!(grid.find_path(Vec2::new(-100.0, -200.0), goal), Some(vec![goal]));
+}
+
+#[test]
+fn path_goes_around_wall() {
+    let clearance = Vec2::new(16.0, 12.0);
+    let grid = NavGrid::new(AREA, CELL_SIZE, clearance, [wall()]);
+    let start = Vec2::new(0.0, -150.0);
+    let goal = Vec2::new(0.0, 150.0);
+    let path = grid.find_path(start, goal).expect("wall can be walked around");
+
+    assert_eq!(path.last(), Some(&goal));
+    let mut from = start;
+    for &waypoint in &path {
+        assert!(grid.line_of_sight(from, waypoint));
+        from = waypoint;
+    }
+    assert!(path.iter().any(|waypoint| waypoint.x.abs() > wall().max.x + clearance.x));
+}
+
+#[test]
+fn enclosed_goal_has_no_path() {
+    let walls = [
+        Rect::new(-100.0, 80.0, 100.0, 100.0),
+        Rect::new(-100.0, -100.0, 100.0, -80.0),
+        Rect::new(-100.0, -100.0, -80.0, 100.0),
+        Rect::new(80.0, -100.0, 100.0, 100.0),
+    ];
+    let grid = NavGrid::new(AREA, CELL_SIZE, Vec2::ZERO, walls);
+
+    assert_eq!(grid.find_path(Vec2::new(0.0, -200.0), Vec2::ZERO), None);
+}
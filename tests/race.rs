@@ -0,0 +1,417 @@
+use bevy::prelude::*;
+use prashan::{
+    GameState,
+    headless::HeadlessApp,
+    obstacle::{ObstacleType, spawn_obstacle},
+    player::{Falldown, Catched, Grabbed, CompletedRace, Disqualified, Score, RaceClock},
+    posts::{PostColor, GateRules, GateResults, MISSED_GATE_PENALTY, spawn_gate},
+    stuneffect::Stun,
+    yeti::{Yeti, YetiState, YetiSettings, Difficulty, PackMember, spawn_yeti},
+    finish::spawn_finish,
+    uicontrols::UiControlType,
+    spatialtree::SpatialTree,
+    jump::Airborne,
+    course::CourseId,
+    ghost::{GhostLibrary, GhostPlugin},
+    splits::{PersonalBests, SplitsPlugin},
+    leaderboard::{Leaderboard, LeaderboardPlugin},
+    storage::{GameStorage, MemoryStorage},
+    replay::{Replay, ReplayFrame, ReplayPlayback},
+    player::SkiProfile,
+    uicontrols::SkiStance,
+    simulation::{Interpolated, TICKS_PER_SECOND},
+};
+
+const MAX_TICKS: usize = 60 * 10;
+
+fn start() -> HeadlessApp {
+    let mut headless = HeadlessApp::new();
+    headless.start_race();
+    assert_eq!(headless.state(), GameState::Playing);
+    headless
+}
+
+#[test]
+fn crashing_into_tree_falls_down_then_stuns() {
+    let mut headless = start();
+    headless.spawn(|commands, game_resources| spawn_obstacle(commands, game_resources, &ObstacleType::Tree, 0.0, -150.0));
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Falldown>(player)));
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Stun>(player)));
+    assert!(!headless.has::<Falldown>(player));
+    assert!(headless.run_until(MAX_TICKS, |headless| !headless.has::<Stun>(player)));
+}
+
+#[test]
+fn steering_avoids_tree() {
+    let mut headless = start();
+    headless.spawn(|commands, game_resources| spawn_obstacle(commands, game_resources, &ObstacleType::Tree, 0.0, -150.0));
+    let player = headless.player().expect("player is spawned");
+
+    headless.steer(Some(UiControlType::Right));
+    headless.run_until(20, |_| false);
+    headless.steer(None);
+
+    assert!(!headless.run_until(MAX_TICKS / 2, |headless| headless.has::<Falldown>(player)));
+}
+
+#[test]
+fn touching_yeti_catches_player() {
+    let mut headless = start();
+    headless.spawn(|commands, game_resources| spawn_yeti(commands, game_resources, &YetiSettings::default(), 0.0, -100.0));
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Catched>(player)));
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.state() == GameState::GameOver));
+}
+
+fn yeti_distance_after_chase(settings: YetiSettings) -> f32 {
+    let mut headless = start();
+    let yeti = headless.spawn(|commands, game_resources| spawn_yeti(commands, game_resources, &settings, 0.0, 600.0));
+    let player = headless.player().expect("player is spawned");
+    headless.run_until(120, |_| false);
+    let position = |entity| headless.app.world.get::<Transform>(entity).unwrap().translation.truncate();
+    position(yeti).distance(position(player))
+}
+
+#[test]
+fn harder_yeti_closes_in_faster() {
+    let easy = yeti_distance_after_chase(YetiSettings { difficulty: Difficulty::Easy, rubber_banding: false });
+    let nightmare = yeti_distance_after_chase(YetiSettings { difficulty: Difficulty::Nightmare, rubber_banding: false });
+
+    assert!(nightmare < easy);
+}
+
+#[test]
+fn rubber_banding_catches_up_from_far_behind() {
+    let normal = yeti_distance_after_chase(YetiSettings::default());
+    let rubber_banding = yeti_distance_after_chase(YetiSettings { rubber_banding: true, ..default() });
+
+    assert!(rubber_banding < normal);
+}
+
+#[test]
+fn yeti_eats_player_before_game_over() {
+    let mut headless = start();
+    let yeti = headless.spawn(|commands, game_resources| spawn_yeti(commands, game_resources, &YetiSettings::default(), 0.0, -100.0));
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Grabbed>(player)));
+    headless.tick();
+    assert_eq!(headless.app.world.get::<Yeti>(yeti).unwrap().state(), YetiState::Eat);
+    assert!(!headless.has::<Catched>(player));
+    assert_eq!(headless.state(), GameState::Playing);
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Catched>(player)));
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.state() == GameState::GameOver));
+}
+
+#[test]
+fn stunned_yeti_recovers() {
+    let mut headless = start();
+    let yeti = headless.spawn(|commands, game_resources| spawn_yeti(commands, game_resources, &YetiSettings::default(), 0.0, -400.0));
+    headless.spawn(|commands, game_resources| spawn_obstacle(commands, game_resources, &ObstacleType::Tree, 0.0, -400.0));
+    headless.tick();
+    headless.app.world.get_mut::<Yeti>(yeti).unwrap().ignore_collisions = Timer::from_seconds(0.0, TimerMode::Once);
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Stun>(yeti)));
+    assert!(headless.run_until(MAX_TICKS, |headless| !headless.has::<Stun>(yeti)));
+    assert_eq!(headless.app.world.get::<Yeti>(yeti).unwrap().state(), YetiState::Recover);
+}
+
+fn spawn_pack_member(headless: &mut HeadlessApp, x: f32, y: f32, flank: f32) -> Entity {
+    headless.spawn(|commands, game_resources| {
+        let yeti = spawn_yeti(commands, game_resources, &YetiSettings::default(), x, y);
+        commands.entity(yeti).insert(PackMember { flank });
+        yeti
+    })
+}
+
+#[test]
+fn only_one_of_the_pack_eats_player() {
+    let mut headless = start();
+    let yetis = [
+        spawn_pack_member(&mut headless, -40.0, -300.0, -1.0),
+        spawn_pack_member(&mut headless, 40.0, -300.0, 1.0),
+    ];
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Grabbed>(player)));
+    headless.tick();
+    let eating = yetis.iter()
+        .filter(|&&yeti| headless.app.world.get::<Yeti>(yeti).unwrap().state() == YetiState::Eat)
+        .count();
+    assert_eq!(eating, 1);
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Catched>(player)));
+}
+
+#[test]
+fn pack_members_keep_apart() {
+    let mut headless = start();
+    let first = spawn_pack_member(&mut headless, 0.0, -800.0, -1.0);
+    let second = spawn_pack_member(&mut headless, 0.0, -800.0, 1.0);
+    headless.run_until(60, |_| false);
+
+    let position = |entity| headless.app.world.get::<Transform>(entity).unwrap().translation.truncate();
+    assert!(position(first).distance(position(second)) > 40.0);
+}
+
+#[test]
+fn yeti_runs_around_wall_of_trees() {
+    let mut headless = start();
+    for x in (-4..=4).map(|i| i as f32 * 24.0) {
+        headless.spawn(|commands, game_resources| spawn_obstacle(commands, game_resources, &ObstacleType::Tree, x, -500.0));
+    }
+    let yeti = headless.spawn(|commands, game_resources| spawn_yeti(commands, game_resources, &YetiSettings::default(), 0.0, -800.0));
+    headless.tick();
+    headless.app.world.get_mut::<Yeti>(yeti).unwrap().ignore_collisions = Timer::from_seconds(0.0, TimerMode::Once);
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| {
+        assert!(!headless.has::<Stun>(yeti), "yeti ran into the trees");
+        headless.has::<Catched>(player)
+    }));
+}
+
+#[test]
+fn crossing_finish_completes_race() {
+    let mut headless = start();
+    headless.spawn(|commands, _| spawn_finish(commands, 0.0, -200.0, 1000.0));
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<CompletedRace>(player)));
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.state() == GameState::GameOver));
+}
+
+#[test]
+fn ghost_is_stored_with_personal_best_time() {
+    let mut headless = HeadlessApp::new();
+    headless.app
+        .insert_resource(GameStorage(Box::new(MemoryStorage::default())))
+        .insert_resource(CourseId::Seed(1))
+        .add_plugin(SplitsPlugin)
+        .add_plugin(GhostPlugin);
+    headless.start_race();
+    headless.spawn(|commands, _| spawn_finish(commands, 0.0, -200.0, 1000.0));
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.state() == GameState::GameOver));
+    let best = headless.app.world.resource::<PersonalBests>().get(&CourseId::Seed(1)).map(|best| best.time);
+    let ghost = headless.app.world.resource::<GhostLibrary>().get(&CourseId::Seed(1)).map(|ghost| ghost.time);
+    assert!(best.is_some());
+    assert_eq!(ghost, best);
+}
+
+/// Plays half a minute of a replay, long enough for the first wave, with `ticks_per_frame` fixed steps in every frame.
+fn yeti_positions_after_replay(ticks_per_frame: u32) -> Vec<Vec2> {
+    let settings = YetiSettings { difficulty: Difficulty::Nightmare, rubber_banding: false };
+    let ticks = 30 * TICKS_PER_SECOND as u32;
+    let frames = (0..ticks)
+        .map(|tick| ReplayFrame {
+            control_type: if tick % 120 < 40 { Some(UiControlType::Left) } else { None },
+            steering: 0.0,
+            stance: SkiStance::Normal,
+            delta: 1.0 / TICKS_PER_SECOND,
+        })
+        .collect();
+    let replay = Replay {
+        course: CourseId::Seed(1),
+        ski_profile: SkiProfile::default(),
+        yeti_settings: settings,
+        gate_rules: GateRules::default(),
+        frames,
+    };
+
+    let mut headless = HeadlessApp::new();
+    headless.app
+        .insert_resource(CourseId::Seed(1))
+        .insert_resource(ReplayPlayback::new(replay));
+    headless.start_race();
+    for _ in 0..ticks / ticks_per_frame {
+        headless.advance(ticks_per_frame);
+    }
+    headless.app.world.query_filtered::<&Interpolated, With<Yeti>>()
+        .iter(&headless.app.world)
+        .map(|interpolated| interpolated.current())
+        .collect()
+}
+
+#[test]
+fn yeti_spawns_do_not_depend_on_frame_rate() {
+    let smooth = yeti_positions_after_replay(1);
+    let choppy = yeti_positions_after_replay(4);
+    assert!(!smooth.is_empty());
+    assert_eq!(smooth, choppy);
+}
+
+#[test]
+fn race_clock_stops_at_finish() {
+    let mut headless = start();
+    headless.spawn(|commands, _| spawn_finish(commands, 0.0, -200.0, 1000.0));
+    let player = headless.player().expect("player is spawned");
+    let race_time = |headless: &HeadlessApp| headless.app.world.get::<RaceClock>(player).unwrap().time();
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<CompletedRace>(player)));
+    let finish_time = race_time(&headless);
+    assert!(finish_time > 0.0);
+    headless.tick();
+    headless.tick();
+    assert_eq!(race_time(&headless), finish_time);
+}
+
+#[test]
+fn obstacles_leave_spatial_tree_after_race() {
+    let mut headless = start();
+    headless.spawn(|commands, _| spawn_finish(commands, 0.0, -100.0, 1000.0));
+    headless.spawn(|commands, game_resources| spawn_obstacle(commands, game_resources, &ObstacleType::Stone, 300.0, -50.0));
+    headless.tick();
+    assert_eq!(headless.app.world.resource::<SpatialTree>().len(), 1);
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.state() == GameState::GameOver));
+    headless.app.world.resource_mut::<NextState<GameState>>().set(GameState::MainMenu);
+    headless.tick();
+    assert!(headless.app.world.resource::<SpatialTree>().is_empty());
+}
+
+fn gate_results(headless: &HeadlessApp) -> (usize, usize) {
+    let gate_results = headless.app.world.resource::<GateResults>();
+    (gate_results.passed, gate_results.missed)
+}
+
+#[test]
+fn skiing_through_gate_passes_it() {
+    let mut headless = start();
+    headless.spawn(|commands, game_resources| spawn_gate(commands, game_resources, &PostColor::Blue, 0.0, -150.0));
+
+    assert!(headless.run_until(MAX_TICKS, |headless| gate_results(headless) != (0, 0)));
+    assert_eq!(gate_results(&headless), (1, 0));
+    assert_eq!(headless.app.world.resource::<GateResults>().splits.len(), 1);
+}
+
+#[test]
+fn skiing_around_gate_adds_penalty() {
+    let mut headless = start();
+    headless.spawn(|commands, game_resources| spawn_gate(commands, game_resources, &PostColor::Red, 200.0, -150.0));
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| gate_results(headless) != (0, 0)));
+    assert_eq!(gate_results(&headless), (0, 1));
+    assert!(headless.app.world.get::<Score>(player).unwrap().points() < 0);
+    assert!(headless.app.world.get::<RaceClock>(player).unwrap().time() >= MISSED_GATE_PENALTY);
+    assert!(!headless.has::<Disqualified>(player));
+}
+
+#[test]
+fn missed_gate_disqualifies_in_strict_mode() {
+    let mut headless = HeadlessApp::new();
+    headless.app.insert_resource(GateRules::Strict);
+    headless.start_race();
+    headless.spawn(|commands, game_resources| spawn_gate(commands, game_resources, &PostColor::Red, 200.0, -150.0));
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Disqualified>(player)));
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.state() == GameState::GameOver));
+}
+
+#[test]
+fn disqualified_run_over_the_finish_is_not_recorded() {
+    let mut headless = HeadlessApp::new();
+    headless.app
+        .insert_resource(GateRules::Strict)
+        .insert_resource(GameStorage(Box::new(MemoryStorage::default())))
+        .insert_resource(CourseId::Seed(1))
+        .add_event::<ReceivedCharacter>()
+        .add_plugin(SplitsPlugin)
+        .add_plugin(LeaderboardPlugin);
+    headless.start_race();
+    headless.spawn(|commands, game_resources| spawn_gate(commands, game_resources, &PostColor::Red, 200.0, -150.0));
+    headless.spawn(|commands, _| spawn_finish(commands, 0.0, -151.0, 1000.0));
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.state() == GameState::GameOver));
+    headless.app.world.resource_mut::<NextState<GameState>>().set(GameState::MainMenu);
+    headless.tick();
+
+    assert!(headless.app.world.resource::<PersonalBests>().get(&CourseId::Seed(1)).is_none());
+    assert!(headless.app.world.resource::<Leaderboard>().entries(&CourseId::Seed(1)).is_empty());
+}
+
+fn launch_from_ramp() -> (HeadlessApp, Entity) {
+    let mut headless = start();
+    headless.spawn(|commands, game_resources| spawn_obstacle(commands, game_resources, &ObstacleType::Ramp, 0.0, -100.0));
+    let player = headless.player().expect("player is spawned");
+
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Airborne>(player)));
+    (headless, player)
+}
+
+#[test]
+fn ramp_jumps_over_tree() {
+    let (mut headless, player) = launch_from_ramp();
+    let position = headless.app.world.get::<Transform>(player).unwrap().translation;
+    headless.spawn(|commands, game_resources| spawn_obstacle(commands, game_resources, &ObstacleType::Tree, position.x, position.y - 15.0));
+
+    assert!(headless.run_until(MAX_TICKS, |headless| !headless.has::<Airborne>(player)));
+    assert!(!headless.run_until(30, |headless| headless.has::<Falldown>(player)));
+}
+
+#[test]
+fn landing_across_flight_direction_falls() {
+    let (mut headless, player) = launch_from_ramp();
+
+    headless.steer(Some(UiControlType::Right));
+    assert!(headless.run_until(MAX_TICKS, |headless| !headless.has::<Airborne>(player)));
+    headless.tick();
+    assert!(headless.has::<Falldown>(player));
+}
+
+#[test]
+fn pause_freezes_race_until_resumed() {
+    let mut headless = start();
+    let player = headless.player().expect("player is spawned");
+    headless.run_until(30, |_| false);
+    let position = |headless: &HeadlessApp| headless.app.world.get::<Transform>(player).unwrap().translation;
+    let clock = |headless: &HeadlessApp| headless.app.world.get::<RaceClock>(player).unwrap().time();
+
+    headless.toggle_pause();
+    assert_eq!(headless.state(), GameState::Paused);
+    let (paused_position, paused_clock) = (position(&headless), clock(&headless));
+    headless.run_until(120, |_| false);
+    assert_eq!(position(&headless), paused_position);
+    assert_eq!(clock(&headless), paused_clock);
+
+    headless.toggle_pause();
+    assert_eq!(headless.state(), GameState::Playing);
+    assert_eq!(headless.player(), Some(player));
+    headless.run_until(30, |_| false);
+    assert!(position(&headless).y < paused_position.y);
+    assert!(clock(&headless) > paused_clock);
+}
+
+#[test]
+fn stun_does_not_wear_off_while_paused() {
+    let mut headless = start();
+    headless.spawn(|commands, game_resources| spawn_obstacle(commands, game_resources, &ObstacleType::Tree, 0.0, -150.0));
+    let player = headless.player().expect("player is spawned");
+    assert!(headless.run_until(MAX_TICKS, |headless| headless.has::<Stun>(player)));
+
+    headless.toggle_pause();
+    headless.run_until(MAX_TICKS, |_| false);
+    headless.toggle_pause();
+
+    assert!(headless.has::<Stun>(player));
+    assert!(headless.run_until(MAX_TICKS, |headless| !headless.has::<Stun>(player)));
+}
+
+#[test]
+fn restart_from_pause_starts_a_new_race() {
+    let mut headless = start();
+    let player = headless.player().expect("player is spawned");
+    headless.toggle_pause();
+
+    headless.app.world.resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    headless.tick();
+
+    assert_eq!(headless.state(), GameState::Playing);
+    let restarted = headless.player().expect("player is spawned again");
+    assert_ne!(restarted, player);
+}
@@ -0,0 +1,16 @@
+use prashan::{
+    settings::Settings,
+    storage::{MemoryStorage, Storage},
+    yeti::Difficulty,
+};
+
+#[test]
+fn missing_settings_fields_take_defaults() {
+    let storage = MemoryStorage::default();
+    storage.save("settings", "(large_text: true)").unwrap();
+
+    let settings = Settings::load(&storage);
+    assert!(settings.large_text);
+    assert!(!settings.reduced_motion);
+    assert_eq!(settings.difficulty, Difficulty::default());
+}
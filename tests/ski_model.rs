@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+use prashan::{
+    course::CourseId,
+    headless::HeadlessApp,
+    player::{Player, Rotation, SkiProfile},
+    posts::GateRules,
+    surface::{SurfaceType, spawn_surface},
+    replay::{Replay, ReplayFrame},
+    storage::MemoryStorage,
+    uicontrols::{SkiStance, UiControlType},
+    yeti::{Difficulty, YetiSettings},
+};
+
+fn speed_after(ski_profile: SkiProfile, stance: SkiStance, ticks: usize) -> f32 {
+    let mut headless = HeadlessApp::new();
+    headless.app.insert_resource(ski_profile);
+    headless.start_race();
+    headless.stance(stance);
+    headless.run_until(ticks, |_| false);
+    let player = headless.player().expect("player is spawned");
+    headless.app.world.get::<Player>(player).unwrap().speed()
+}
+
+#[test]
+fn tuck_is_faster_and_brake_slower() {
+    let normal = speed_after(SkiProfile::Physics, SkiStance::Normal, 300);
+    let tuck = speed_after(SkiProfile::Physics, SkiStance::Tuck, 300);
+    let brake = speed_after(SkiProfile::Physics, SkiStance::Brake, 300);
+
+    assert!(normal > 0.0);
+    assert!(tuck > normal);
+    assert!(brake < normal);
+}
+
+#[test]
+fn arcade_profile_ignores_stance() {
+    let normal = speed_after(SkiProfile::Arcade, SkiStance::Normal, 120);
+    let tuck = speed_after(SkiProfile::Arcade, SkiStance::Tuck, 120);
+
+    assert!(normal > 0.0);
+    assert_eq!(normal, tuck);
+}
+
+#[test]
+fn speed_is_carried_through_turn() {
+    let mut headless = HeadlessApp::new();
+    headless.start_race();
+    headless.run_until(180, |_| false);
+    let player = headless.player().expect("player is spawned");
+    let speed = |headless: &HeadlessApp| headless.app.world.get::<Player>(player).unwrap().speed();
+    let before_turn = speed(&headless);
+
+    headless.steer(Some(UiControlType::Right));
+    headless.tick();
+    let after_turn = speed(&headless);
+
+    assert!(after_turn > before_turn * 0.9);
+}
+
+#[test]
+fn replay_keeps_stance_and_settings() {
+    let replay = Replay {
+        course: CourseId::Seed(3),
+        ski_profile: SkiProfile::Arcade,
+        yeti_settings: YetiSettings { difficulty: Difficulty::Hard, rubber_banding: true },
+        gate_rules: GateRules::Strict,
+        frames: vec![
+            ReplayFrame { control_type: Some(UiControlType::Left), steering: 0.6, stance: SkiStance::Tuck, delta: 0.5 },
+            ReplayFrame { control_type: None, steering: 0.0, stance: SkiStance::Brake, delta: 0.25 },
+        ],
+    };
+
+    let loaded = Replay::from_bytes(&replay.to_bytes()).expect("replay is valid");
+    assert_eq!(loaded.ski_profile, SkiProfile::Arcade);
+    assert_eq!(loaded.yeti_settings, YetiSettings { difficulty: Difficulty::Hard, rubber_banding: true });
+    assert_eq!(loaded.gate_rules, GateRules::Strict);
+    assert_eq!(loaded.frames.len(), 2);
+    assert_eq!(loaded.frames[0].control_type, Some(UiControlType::Left));
+    assert_eq!(loaded.frames[0].steering, 153.0 / 255.0);
+    assert_eq!(loaded.frames[0].stance, SkiStance::Tuck);
+    assert_eq!(loaded.frames[1].stance, SkiStance::Brake);
+    assert_eq!(loaded.frames[1].delta, 0.25);
+
+    let storage = MemoryStorage::default();
+    replay.save(&storage);
+    let stored = Replay::load(&storage).expect("stored replay is valid");
+    assert_eq!(stored.to_bytes(), replay.to_bytes());
+}
+
+fn rotation_after_tilt(stick_x: f32) -> f32 {
+    let mut headless = HeadlessApp::new();
+    headless.start_race();
+    headless.tilt_stick(stick_x);
+    headless.run_until(10, |_| false);
+    let player = headless.player().expect("player is spawned");
+    headless.app.world.get::<Rotation>(player).unwrap().0
+}
+
+#[test]
+fn half_tilted_stick_turns_slower() {
+    let full = rotation_after_tilt(1.0);
+    let half = rotation_after_tilt(0.5);
+    let left = rotation_after_tilt(-1.0);
+
+    assert!(full > 0.0);
+    assert!(half > 0.0 && half < full * 0.7);
+    assert_eq!(left, -full);
+    assert_eq!(rotation_after_tilt(0.1), 0.0);
+}
+
+fn race_on(surface: Option<SurfaceType>) -> (HeadlessApp, Entity) {
+    let mut headless = HeadlessApp::new();
+    headless.start_race();
+    if let Some(surface) = surface {
+        headless.spawn(|commands, _| spawn_surface(commands, surface, Rect::new(-2000.0, -4000.0, 2000.0, 100.0)));
+    }
+    let player = headless.player().expect("player is spawned");
+    (headless, player)
+}
+
+#[test]
+fn powder_slows_skier_down() {
+    let speed = |surface| {
+        let (mut headless, player) = race_on(surface);
+        headless.run_until(180, |_| false);
+        headless.app.world.get::<Player>(player).unwrap().speed()
+    };
+
+    assert!(speed(Some(SurfaceType::Powder)) < speed(None) * 0.6);
+}
+
+#[test]
+fn ice_reduces_turning() {
+    let rotation = |surface| {
+        let (mut headless, player) = race_on(surface);
+        headless.steer(Some(UiControlType::Right));
+        headless.run_until(20, |_| false);
+        headless.app.world.get::<Rotation>(player).unwrap().0
+    };
+
+    let on_ice = rotation(Some(SurfaceType::Ice));
+    assert!(on_ice > 0.0);
+    assert!(on_ice < rotation(None) * 0.6);
+}
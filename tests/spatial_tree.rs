@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use prashan::{collidable::Collidable, spatialtree::SpatialTree};
+
+#[test]
+fn insert_move_and_remove() {
+    let mut world = World::new();
+    let tree_entity = world.spawn_empty().id();
+    let stone_entity = world.spawn_empty().id();
+    let mut spatial_tree = SpatialTree::new(100.0);
+
+    spatial_tree.insert(tree_entity, Rect::new(-10.0, -10.0, 10.0, 10.0));
+    spatial_tree.insert(stone_entity, Rect::new(390.0, -510.0, 410.0, -490.0));
+    assert_eq!(spatial_tree.len(), 2);
+    assert_eq!(spatial_tree.get_at(Rect::new(-5.0, -5.0, 5.0, 5.0)), vec![tree_entity]);
+    assert_eq!(spatial_tree.get_at(Rect::new(350.0, -550.0, 450.0, -450.0)), vec![stone_entity]);
+
+    spatial_tree.insert(tree_entity, Rect::new(390.0, -310.0, 410.0, -290.0));
+    assert!(spatial_tree.get_at(Rect::new(-5.0, -5.0, 5.0, 5.0)).is_empty());
+    assert_eq!(spatial_tree.get_at(Rect::new(350.0, -550.0, 450.0, -250.0)).len(), 2);
+
+    spatial_tree.remove(tree_entity);
+    spatial_tree.remove(stone_entity);
+    assert!(spatial_tree.is_empty());
+    assert!(spatial_tree.get_at(Rect::new(-1000.0, -1000.0, 1000.0, 1000.0)).is_empty());
+}
+
+fn obstacles(positions: &[(f32, f32)]) -> (SpatialTree, Vec<(Entity, Collidable)>) {
+    let mut world = World::new();
+    let mut spatial_tree = SpatialTree::new(100.0);
+    let obstacles: Vec<(Entity, Collidable)> = positions.iter()
+        .map(|&(x, y)| (world.spawn_empty().id(), Collidable::new(x, y, 10.0, 10.0, 0.0, 0.0)))
+        .collect();
+    for (entity, collidable) in obstacles.iter() {
+        spatial_tree.insert(*entity, collidable.bounds());
+    }
+    (spatial_tree, obstacles)
+}
+
+fn lookup<'a>(obstacles: &'a [(Entity, Collidable)]) -> impl Fn(Entity) -> Option<&'a Collidable> {
+    move |entity| obstacles.iter().find(|(e, _)| *e == entity).map(|(_, collidable)| collidable)
+}
+
+#[test]
+fn intersect_line_returns_entry_point() {
+    let collidable = Collidable::new(0.0, 0.0, 10.0, 10.0, 0.0, 0.0);
+    assert_eq!(collidable.intersect_line(Vec2::new(-50.0, 0.0), Vec2::new(50.0, 0.0)), Some(Vec2::new(-10.0, 0.0)));
+    assert_eq!(collidable.intersect_line(Vec2::new(0.0, 50.0), Vec2::new(0.0, -50.0)), Some(Vec2::new(0.0, 10.0)));
+    assert_eq!(collidable.intersect_line(Vec2::new(-50.0, 20.0), Vec2::new(50.0, 20.0)), None);
+}
+
+#[test]
+fn raycast_returns_first_hit() {
+    let (spatial_tree, obstacles) = obstacles(&[(0.0, -450.0), (0.0, -250.0), (300.0, -100.0)]);
+
+    let hit = spatial_tree.raycast(Vec2::ZERO, Vec2::new(0.0, -600.0), lookup(&obstacles)).expect("ray hits");
+    assert_eq!(hit.entity, obstacles[1].0);
+    assert_eq!(hit.point, Vec2::new(0.0, -240.0));
+
+    let hit = spatial_tree.raycast(Vec2::new(0.0, -600.0), Vec2::ZERO, lookup(&obstacles)).expect("ray hits");
+    assert_eq!(hit.entity, obstacles[0].0);
+
+    assert!(spatial_tree.raycast(Vec2::ZERO, Vec2::new(0.0, -200.0), lookup(&obstacles)).is_none());
+    assert!(spatial_tree.raycast(Vec2::ZERO, Vec2::new(600.0, 0.0), lookup(&obstacles)).is_none());
+    let hit = spatial_tree.raycast(Vec2::ZERO, Vec2::new(600.0, -200.0), lookup(&obstacles)).expect("diagonal ray hits");
+    assert_eq!(hit.entity, obstacles[2].0);
+}
+
+#[test]
+fn overlapping_and_nearest() {
+    let (spatial_tree, obstacles) = obstacles(&[(0.0, 0.0), (45.0, 0.0), (500.0, 0.0), (-900.0, -900.0)]);
+
+    let mut overlapping = spatial_tree.overlapping(Rect::new(-20.0, -20.0, 40.0, 20.0), lookup(&obstacles));
+    overlapping.sort();
+    assert_eq!(overlapping, vec![obstacles[0].0, obstacles[1].0]);
+
+    let point = Vec2::new(480.0, 0.0);
+    assert_eq!(spatial_tree.nearest(point, 2, lookup(&obstacles)), vec![obstacles[2].0, obstacles[1].0]);
+    assert_eq!(spatial_tree.nearest(point, 10, lookup(&obstacles)).len(), 4);
+    assert!(spatial_tree.nearest(point, 0, lookup(&obstacles)).is_empty());
+}
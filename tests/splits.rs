@@ -0,0 +1,18 @@
+use prashan::{
+    course::CourseId,
+    splits::{PersonalBest, PersonalBests},
+};
+
+#[test]
+fn keeps_fastest_run_and_compares_gates() {
+    let mut personal_bests = PersonalBests::default();
+    let course_id = CourseId::Seed(7);
+
+    assert_eq!(personal_bests.delta(&course_id, 0, 3.0), None);
+    assert!(personal_bests.update(&course_id, PersonalBest { time: 20.0, splits: vec![3.0, 6.5] }));
+    assert!(!personal_bests.update(&course_id, PersonalBest { time: 21.0, splits: vec![2.0, 5.0] }));
+
+    assert_eq!(personal_bests.delta(&course_id, 1, 6.0), Some(-0.5));
+    assert_eq!(personal_bests.delta(&course_id, 2, 9.0), None);
+    assert_eq!(personal_bests.delta(&CourseId::Seed(8), 0, 3.0), None);
+}
@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+use prashan::{
+    course::CourseId,
+    storage::{MemoryStorage, Storage, load_ron, save_ron},
+};
+
+#[test]
+fn ron_values_round_trip_through_storage() {
+    let storage = MemoryStorage::default();
+    let empty: HashMap<CourseId, f32> = load_ron(&storage, "times");
+    assert!(empty.is_empty());
+
+    let times = HashMap::from([
+        (CourseId::Seed(1), 12.5),
+        (CourseId::Course("courses/first.course.ron".to_string()), 30.0),
+    ]);
+    save_ron(&storage, "times", &times);
+    assert_eq!(load_ron::<HashMap<CourseId, f32>>(&storage, "times"), times);
+}
+
+#[test]
+fn unreadable_value_loads_default() {
+    let storage = MemoryStorage::default();
+    storage.save("times", "not ron").unwrap();
+    assert!(load_ron::<HashMap<CourseId, f32>>(&storage, "times").is_empty());
+}